@@ -0,0 +1,48 @@
+//! Compares decapsulation from a `SecretKey` with decapsulation from an
+//! `ExpandedSecretKey`. Run with `cargo bench --bench decapsulation`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rmce::*;
+
+const ITERATIONS: u32 = 50;
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+  f();
+  let start = Instant::now();
+  for _ in 0..ITERATIONS {
+    f();
+  }
+  start.elapsed() / ITERATIONS
+}
+
+fn main() {
+  let (pk, sk) = generate_keypair_from_seed([7u8; SecretKey::SEED_SIZE]);
+  let mut n = 0u8;
+  let (ct, _) = pk.encapsulate_with_entropy_provider(|data| {
+    for b in data {
+      *b = n;
+      n = n.wrapping_add(1);
+    }
+  });
+  let esk = sk.expand();
+
+  let plain = time(|| {
+    black_box(black_box(&ct).decapsulate(black_box(&sk)));
+  });
+  let expanded = time(|| {
+    black_box(black_box(&ct).decapsulate_expanded(black_box(&esk)));
+  });
+  let expand = time(|| {
+    black_box(black_box(&sk).expand());
+  });
+
+  println!("decapsulate           {plain:>12?}/iter");
+  println!("decapsulate_expanded  {expanded:>12?}/iter");
+  println!("expand                {expand:>12?}/iter");
+  println!(
+    "speedup               {:>12.2}x",
+    plain.as_secs_f64() / expanded.as_secs_f64()
+  );
+}
@@ -0,0 +1,146 @@
+//! Recipient and identity encodings and file key wrapping for the
+//! `age-plugin-mceliece` [age](https://age-encryption.org) plugin.
+//!
+//! Recipients are the Bech32 encoding of a [`PublicKey`] with the
+//! human-readable part `age1mceliece`, identities that of a [`SecretKey`]
+//! with `AGE-PLUGIN-MCELIECE-`, upper-cased as age expects. Bech32 strings
+//! are normally capped at 90 characters, but age does not enforce the limit
+//! and a recipient is over two million characters long.
+//!
+//! # Stanza
+//!
+//! ```text
+//! -> mceliece base64(ShareableSecret)
+//! base64(ChaCha20-Poly1305(file key))
+//! ```
+//!
+//! The wrapping key is 32 bytes of SHAKE256 over `"rmce-age-v1"`, the
+//! [`crate::SharedKey`] and the [`ShareableSecret`]. Every stanza has its own
+//! key, so the nonce is all zeros.
+
+use base64::{engine::general_purpose::STANDARD_NO_PAD as BASE64, Engine};
+use bech32::{FromBase32, ToBase32, Variant};
+use chacha20poly1305::{
+  aead::{Aead, KeyInit},
+  ChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  impls::libkeccak::{Sponge, SHAKE256_RATE, SHAKE_SUFFIX},
+  Error, ExpandedSecretKey, PublicKey, SecretKey, ShareableSecret, SharedKey,
+};
+
+/// Name of the plugin, which age derives from the recipient and identity
+/// prefixes to find the `age-plugin-mceliece` binary.
+pub const PLUGIN_NAME: &str = "mceliece";
+/// Human-readable part of recipients.
+pub const RECIPIENT_HRP: &str = "age1mceliece";
+/// Human-readable part of identities.
+pub const IDENTITY_HRP: &str = "age-plugin-mceliece-";
+/// Tag of the stanzas this plugin produces.
+pub const STANZA_TAG: &str = "mceliece";
+/// Length of an age file key.
+pub const FILE_KEY_LEN: usize = 16;
+
+const DOMAIN: &[u8] = b"rmce-age-v1";
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+const NONCE: [u8; 12] = [0u8; 12];
+
+/// A recipient stanza of an age header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stanza {
+  pub tag: String,
+  pub args: Vec<String>,
+  pub body: Vec<u8>,
+}
+
+pub fn encode_recipient(pk: &PublicKey) -> String {
+  bech32::encode(RECIPIENT_HRP, pk.as_bytes().to_base32(), Variant::Bech32).unwrap()
+}
+
+pub fn decode_recipient(recipient: &str) -> Result<PublicKey, Error> {
+  PublicKey::try_from(decode(recipient, RECIPIENT_HRP)?)
+}
+
+pub fn encode_identity(sk: &SecretKey) -> Zeroizing<String> {
+  let identity = Zeroizing::new(
+    bech32::encode(IDENTITY_HRP, sk.as_bytes().to_base32(), Variant::Bech32).unwrap(),
+  );
+  Zeroizing::new(identity.to_uppercase())
+}
+
+pub fn decode_identity(identity: &str) -> Result<SecretKey, Error> {
+  SecretKey::try_from(Zeroizing::new(decode(identity, IDENTITY_HRP)?).as_slice())
+}
+
+fn decode(s: &str, expected_hrp: &str) -> Result<Vec<u8>, Error> {
+  let (hrp, data, variant) = bech32::decode(s).map_err(|_| Error::InvalidEncoding)?;
+  if hrp != expected_hrp || variant != Variant::Bech32 {
+    return Err(Error::InvalidEncoding);
+  }
+  Vec::<u8>::from_base32(&data).map_err(|_| Error::InvalidEncoding)
+}
+
+/// Wraps an age file key to `pk`.
+#[cfg(feature = "openssl")]
+pub fn wrap_file_key(pk: &PublicKey, file_key: &[u8; FILE_KEY_LEN]) -> Stanza {
+  wrap_file_key_with_entropy_provider(pk, file_key, crate::crypto_random)
+}
+
+pub fn wrap_file_key_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  file_key: &[u8; FILE_KEY_LEN],
+  entropy_provider: F,
+) -> Stanza {
+  let (enc, key) = pk.encapsulate_with_entropy_provider(entropy_provider);
+  let body = cipher(&key, &enc)
+    .encrypt((&NONCE).into(), &file_key[..])
+    .unwrap();
+  Stanza {
+    tag: STANZA_TAG.to_owned(),
+    args: vec![BASE64.encode(enc.as_bytes())],
+    body,
+  }
+}
+
+/// Recovers the file key from a stanza produced by [`wrap_file_key`].
+///
+/// Fails with [`Error::InvalidHeader`] when the stanza is not a well-formed
+/// `mceliece` stanza and with [`Error::DecryptionFailed`] when it was wrapped
+/// to another key.
+pub fn unwrap_file_key(
+  sk: &ExpandedSecretKey,
+  stanza: &Stanza,
+) -> Result<Zeroizing<[u8; FILE_KEY_LEN]>, Error> {
+  if stanza.tag != STANZA_TAG || stanza.args.len() != 1 {
+    return Err(Error::InvalidHeader);
+  }
+  if stanza.body.len() != FILE_KEY_LEN + TAG_LEN {
+    return Err(Error::InvalidHeader);
+  }
+  let enc = BASE64
+    .decode(&stanza.args[0])
+    .map_err(|_| Error::InvalidHeader)?;
+  let enc = ShareableSecret::try_from(&enc[..]).map_err(|_| Error::InvalidHeader)?;
+  let key = enc
+    .try_decapsulate_expanded(sk)
+    .map_err(|_| Error::InvalidHeader)?;
+  let file_key = Zeroizing::new(
+    cipher(&key, &enc)
+      .decrypt((&NONCE).into(), &stanza.body[..])
+      .map_err(|_| Error::DecryptionFailed)?,
+  );
+  Ok(Zeroizing::new(file_key[..].try_into().unwrap()))
+}
+
+fn cipher(key: &SharedKey, enc: &ShareableSecret) -> ChaCha20Poly1305 {
+  let mut wrap_key = Zeroizing::new([0u8; KEY_LEN]);
+  let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+  hash.absorb(DOMAIN);
+  hash.absorb(key.as_bytes());
+  hash.absorb(enc.as_bytes());
+  hash.squeeze(wrap_key.as_mut());
+  ChaCha20Poly1305::new_from_slice(wrap_key.as_ref()).unwrap()
+}
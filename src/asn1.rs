@@ -0,0 +1,126 @@
+//! DER and PEM encodings of keys: X.509 `SubjectPublicKeyInfo` for
+//! [`PublicKey`] and PKCS#8 `PrivateKeyInfo` for [`SecretKey`].
+//!
+//! The `AlgorithmIdentifier` carries the OID of the parameter set, see
+//! [`ParameterSet::oid`], and no parameters. `subjectPublicKey` and
+//! `privateKey` hold the raw key bytes of [`PublicKey::as_bytes`] and
+//! [`SecretKey::as_bytes`]. The encodings are provided through the
+//! [`EncodePublicKey`], [`DecodePublicKey`], [`EncodePrivateKey`] and
+//! [`DecodePrivateKey`] traits.
+//!
+//! Decoding fails with [`spki::Error::OidUnknown`] when the OID is not that of
+//! mceliece8192128f, even if it names another Classic McEliece parameter set.
+
+pub use pkcs8::{
+  self, der, spki, DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey,
+  LineEnding, ObjectIdentifier,
+};
+use pkcs8::{
+  der::{asn1::BitStringRef, AnyRef, Document, SecretDocument},
+  spki::{AlgorithmIdentifierRef, AssociatedAlgorithmIdentifier, SubjectPublicKeyInfoRef},
+  PrivateKeyInfo,
+};
+
+pub use crate::{ParameterSet, PARAMETER_SET};
+use crate::{PublicKey, SecretKey};
+
+/// Provisional OIDs of the parameter sets, under `1.3.6.1.4.1.22554.5.1`.
+///
+/// No standard assigns OIDs to Classic McEliece yet and this project has no
+/// arc of its own. `22554` is the private enterprise number of Bouncy Castle,
+/// which numbers the Classic McEliece parameter sets this way; reusing its OIDs
+/// keeps keys and certificates readable by other implementations that do the
+/// same, instead of minting OIDs nobody else knows. The values will change
+/// once official OIDs are allocated.
+impl ParameterSet {
+  pub const fn oid(self) -> ObjectIdentifier {
+    match self {
+      Self::Mceliece348864 => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.1"),
+      Self::Mceliece348864f => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.2"),
+      Self::Mceliece460896 => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.3"),
+      Self::Mceliece460896f => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.4"),
+      Self::Mceliece6688128 => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.5"),
+      Self::Mceliece6688128f => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.6"),
+      Self::Mceliece6960119 => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.7"),
+      Self::Mceliece6960119f => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.8"),
+      Self::Mceliece8192128 => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.9"),
+      Self::Mceliece8192128f => ObjectIdentifier::new_unwrap("1.3.6.1.4.1.22554.5.1.10"),
+    }
+  }
+  pub fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+    Self::ALL.into_iter().find(|set| set.oid() == *oid)
+  }
+}
+
+/// OID of [`PARAMETER_SET`].
+pub const OID: ObjectIdentifier = PARAMETER_SET.oid();
+/// `AlgorithmIdentifier` of keys of [`PARAMETER_SET`].
+pub const ALGORITHM_IDENTIFIER: AlgorithmIdentifierRef<'static> = AlgorithmIdentifierRef {
+  oid: OID,
+  parameters: None,
+};
+
+/* the OID must match and the parameters must be absent */
+fn check_algorithm(algorithm: &AlgorithmIdentifierRef<'_>) -> spki::Result<()> {
+  algorithm.assert_algorithm_oid(OID)?;
+  if algorithm.parameters.is_some() {
+    return Err(spki::Error::KeyMalformed);
+  }
+  Ok(())
+}
+
+impl AssociatedAlgorithmIdentifier for PublicKey {
+  type Params = AnyRef<'static>;
+  const ALGORITHM_IDENTIFIER: AlgorithmIdentifierRef<'static> = ALGORITHM_IDENTIFIER;
+}
+
+impl AssociatedAlgorithmIdentifier for SecretKey {
+  type Params = AnyRef<'static>;
+  const ALGORITHM_IDENTIFIER: AlgorithmIdentifierRef<'static> = ALGORITHM_IDENTIFIER;
+}
+
+impl EncodePublicKey for PublicKey {
+  fn to_public_key_der(&self) -> spki::Result<Document> {
+    let spki = SubjectPublicKeyInfoRef {
+      algorithm: ALGORITHM_IDENTIFIER,
+      subject_public_key: BitStringRef::from_bytes(self.as_bytes())?,
+    };
+    Ok(Document::encode_msg(&spki)?)
+  }
+}
+
+impl TryFrom<SubjectPublicKeyInfoRef<'_>> for PublicKey {
+  type Error = spki::Error;
+  fn try_from(spki: SubjectPublicKeyInfoRef<'_>) -> spki::Result<Self> {
+    check_algorithm(&spki.algorithm)?;
+    let bytes = spki
+      .subject_public_key
+      .as_bytes()
+      .ok_or(spki::Error::KeyMalformed)?;
+    PublicKey::try_from(bytes).map_err(|_| spki::Error::KeyMalformed)
+  }
+}
+
+impl EncodePrivateKey for SecretKey {
+  fn to_pkcs8_der(&self) -> pkcs8::Result<SecretDocument> {
+    let info = PrivateKeyInfo::new(ALGORITHM_IDENTIFIER, self.as_bytes());
+    Ok(SecretDocument::encode_msg(&info)?)
+  }
+}
+
+/// A public key embedded in a version 2 `OneAsymmetricKey` must be the one
+/// of the secret key.
+impl TryFrom<PrivateKeyInfo<'_>> for SecretKey {
+  type Error = pkcs8::Error;
+  fn try_from(info: PrivateKeyInfo<'_>) -> pkcs8::Result<Self> {
+    check_algorithm(&info.algorithm)?;
+    let sk = SecretKey::try_from(info.private_key).map_err(|_| pkcs8::Error::KeyMalformed)?;
+    if let Some(public_key) = info.public_key {
+      let pk = sk.public_key().map_err(|_| pkcs8::Error::KeyMalformed)?;
+      if public_key != &pk.as_bytes()[..] {
+        return Err(pkcs8::Error::KeyMalformed);
+      }
+    }
+    Ok(sk)
+  }
+}
@@ -0,0 +1,331 @@
+use std::{
+  collections::BTreeMap,
+  io::{self, BufRead, Read, Write},
+  process::ExitCode,
+};
+
+use base64::{engine::general_purpose::STANDARD_NO_PAD as BASE64, Engine};
+use rmce::age::{
+  decode_identity, decode_recipient, encode_identity, encode_recipient, unwrap_file_key,
+  wrap_file_key, Stanza, FILE_KEY_LEN, STANZA_TAG,
+};
+use rmce::{Error, ExpandedSecretKey, PublicKey};
+use zeroize::Zeroizing;
+
+const USAGE: &str = "\
+Usage:
+  age-plugin-mceliece [--generate]   print a new identity and its recipient
+  age-plugin-mceliece -y             print the recipient of the identity on stdin
+
+Use the printed recipient with `age -r` and the identity file with `age -i`.";
+
+const COLUMNS: usize = 64;
+
+fn main() -> ExitCode {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let args: Vec<&str> = args.iter().map(String::as_str).collect();
+  let result = match args[..] {
+    [] | ["--generate"] => generate(),
+    ["-y"] => to_recipient(),
+    ["--age-plugin=recipient-v1"] => recipient_v1(&mut Connection::new()),
+    ["--age-plugin=identity-v1"] => identity_v1(&mut Connection::new()),
+    ["-h" | "--help"] => {
+      println!("{USAGE}");
+      Ok(())
+    }
+    _ => {
+      eprintln!("{USAGE}");
+      return ExitCode::from(2);
+    }
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("age-plugin-mceliece: {err}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn generate() -> io::Result<()> {
+  let (pk, sk) = rmce::generate_keypair();
+  let mut out = io::stdout().lock();
+  writeln!(out, "# recipient: {}", encode_recipient(&pk))?;
+  writeln!(out, "{}", *encode_identity(&sk))?;
+  out.flush()
+}
+
+fn to_recipient() -> io::Result<()> {
+  let mut input = Zeroizing::new(String::new());
+  io::stdin().read_to_string(&mut input)?;
+  let mut out = io::stdout().lock();
+  for line in input.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let pk = decode_identity(line)
+      .and_then(|sk| sk.public_key())
+      .map_err(invalid_data)?;
+    writeln!(out, "{}", encode_recipient(&pk))?;
+  }
+  out.flush()
+}
+
+fn invalid_data(err: Error) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// One side of the plugin protocol, speaking stanzas over stdin and stdout.
+struct Connection<R: BufRead, W: Write> {
+  input: R,
+  output: W,
+}
+
+impl Connection<io::StdinLock<'static>, io::StdoutLock<'static>> {
+  fn new() -> Self {
+    Self {
+      input: io::stdin().lock(),
+      output: io::stdout().lock(),
+    }
+  }
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+  fn read_line(&mut self) -> io::Result<Zeroizing<String>> {
+    let mut line = Zeroizing::new(String::new());
+    if self.input.read_line(&mut line)? == 0 {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    if line.pop() != Some('\n') {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(line)
+  }
+  fn receive(&mut self) -> io::Result<Stanza> {
+    let line = self.read_line()?;
+    let mut words = line
+      .strip_prefix("-> ")
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed stanza"))?
+      .split(' ')
+      .map(str::to_owned);
+    let tag = words.next().unwrap();
+    let args = words.collect();
+
+    /* the body ends with its first line shorter than a full one */
+    let mut body = Zeroizing::new(String::new());
+    loop {
+      let line = self.read_line()?;
+      body.push_str(&line);
+      if line.len() < COLUMNS {
+        break;
+      }
+    }
+    let body = BASE64
+      .decode(body.as_bytes())
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "malformed stanza body"))?;
+    Ok(Stanza { tag, args, body })
+  }
+  fn send(&mut self, tag: &str, args: &[&str], body: &[u8]) -> io::Result<()> {
+    let mut header = String::from("-> ");
+    header.push_str(tag);
+    for arg in args {
+      header.push(' ');
+      header.push_str(arg);
+    }
+    header.push('\n');
+    self.output.write_all(header.as_bytes())?;
+
+    let body = Zeroizing::new(BASE64.encode(body));
+    for line in body.as_bytes().chunks(COLUMNS) {
+      self.output.write_all(line)?;
+      self.output.write_all(b"\n")?;
+    }
+    if body.len().is_multiple_of(COLUMNS) {
+      self.output.write_all(b"\n")?;
+    }
+    self.output.flush()
+  }
+  /* sends a command of the bidirectional phase and waits for the client's answer */
+  fn command(&mut self, tag: &str, args: &[&str], body: &[u8]) -> io::Result<Stanza> {
+    self.send(tag, args, body)?;
+    self.receive()
+  }
+  fn done(&mut self) -> io::Result<()> {
+    self.send("done", &[], &[])
+  }
+}
+
+fn recipient_v1<R: BufRead, W: Write>(conn: &mut Connection<R, W>) -> io::Result<()> {
+  let mut recipients = Vec::new();
+  let mut identities = Vec::new();
+  let mut file_keys = Vec::new();
+  let mut labels = false;
+  loop {
+    let stanza = conn.receive()?;
+    match stanza.tag.as_str() {
+      "add-recipient" => recipients.push(stanza.args),
+      "add-identity" => identities.push(stanza.args),
+      "wrap-file-key" => file_keys.push(Zeroizing::new(stanza.body)),
+      "extension-labels" => labels = true,
+      "done" => break,
+      /* unknown commands, grease included, are ignored */
+      _ => {}
+    }
+  }
+
+  let mut pks = Vec::new();
+  let mut errors = Vec::new();
+  for (index, args) in recipients.iter().enumerate() {
+    match parse_recipient(args) {
+      Ok(pk) => pks.push(pk),
+      Err(err) => errors.push(("recipient", index, format!("invalid recipient: {err}"))),
+    }
+  }
+  for (index, args) in identities.iter().enumerate() {
+    let pk = parse_identity(args).and_then(|sk| sk.public_key());
+    match pk {
+      Ok(pk) => pks.push(pk),
+      Err(err) => errors.push(("identity", index, format!("invalid identity: {err}"))),
+    }
+  }
+  if file_keys.iter().any(|key| key.len() != FILE_KEY_LEN) {
+    errors.push(("internal", 0, "file key of the wrong length".to_owned()));
+  }
+  if !errors.is_empty() {
+    for (kind, index, message) in errors {
+      let index = index.to_string();
+      let args = if kind == "internal" {
+        vec![kind]
+      } else {
+        vec![kind, &index]
+      };
+      conn.command("error", &args, message.as_bytes())?;
+    }
+    return conn.done();
+  }
+
+  if labels {
+    conn.command("labels", &["postquantum"], &[])?;
+  }
+  for (file_index, file_key) in file_keys.iter().enumerate() {
+    let file_key: &[u8; FILE_KEY_LEN] = file_key[..].try_into().unwrap();
+    let file_index = file_index.to_string();
+    for pk in &pks {
+      let stanza = wrap_file_key(pk, file_key);
+      let mut args = vec![file_index.as_str(), stanza.tag.as_str()];
+      args.extend(stanza.args.iter().map(String::as_str));
+      conn.command("recipient-stanza", &args, &stanza.body)?;
+    }
+  }
+  conn.done()
+}
+
+fn identity_v1<R: BufRead, W: Write>(conn: &mut Connection<R, W>) -> io::Result<()> {
+  let mut identities = Vec::new();
+  let mut files: BTreeMap<usize, Vec<Stanza>> = BTreeMap::new();
+  let mut malformed = false;
+  loop {
+    let stanza = conn.receive()?;
+    match stanza.tag.as_str() {
+      "add-identity" => identities.push(stanza.args),
+      "recipient-stanza" => {
+        let mut args = stanza.args.into_iter();
+        let file_index = args.next().and_then(|index| index.parse().ok());
+        let tag = args.next();
+        match (file_index, tag) {
+          (Some(file_index), Some(tag)) => files.entry(file_index).or_default().push(Stanza {
+            tag,
+            args: args.collect(),
+            body: stanza.body,
+          }),
+          _ => malformed = true,
+        }
+      }
+      "done" => break,
+      _ => {}
+    }
+  }
+
+  let mut sks = Vec::new();
+  let mut failed = malformed;
+  if malformed {
+    conn.command(
+      "error",
+      &["internal"],
+      b"recipient-stanza needs a file index and a stanza tag",
+    )?;
+  }
+  for (index, args) in identities.iter().enumerate() {
+    match parse_identity(args) {
+      Ok(sk) => sks.push(sk.expand()),
+      Err(err) => {
+        failed = true;
+        let message = format!("invalid identity: {err}");
+        conn.command(
+          "error",
+          &["identity", &index.to_string()],
+          message.as_bytes(),
+        )?;
+      }
+    }
+  }
+  if failed {
+    return conn.done();
+  }
+
+  for (file_index, stanzas) in &files {
+    let file_index = file_index.to_string();
+    'file: for (stanza_index, stanza) in stanzas.iter().enumerate() {
+      if stanza.tag != STANZA_TAG {
+        continue;
+      }
+      match unwrap(&sks, stanza) {
+        Ok(Some(file_key)) => {
+          conn.command("file-key", &[&file_index], &file_key[..])?;
+          break 'file;
+        }
+        Ok(None) => {}
+        Err(err) => {
+          let message = format!("malformed {STANZA_TAG} stanza: {err}");
+          let stanza_index = stanza_index.to_string();
+          conn.command(
+            "error",
+            &["stanza", &file_index, &stanza_index],
+            message.as_bytes(),
+          )?;
+          break 'file;
+        }
+      }
+    }
+  }
+  conn.done()
+}
+
+/* Ok(None) when the stanza was wrapped to none of the identities */
+fn unwrap(
+  sks: &[ExpandedSecretKey],
+  stanza: &Stanza,
+) -> Result<Option<Zeroizing<[u8; FILE_KEY_LEN]>>, Error> {
+  for sk in sks {
+    match unwrap_file_key(sk, stanza) {
+      Ok(file_key) => return Ok(Some(file_key)),
+      Err(Error::DecryptionFailed) => {}
+      Err(err) => return Err(err),
+    }
+  }
+  Ok(None)
+}
+
+fn parse_recipient(args: &[String]) -> Result<PublicKey, Error> {
+  match args {
+    [recipient] => decode_recipient(recipient),
+    _ => Err(Error::InvalidEncoding),
+  }
+}
+
+fn parse_identity(args: &[String]) -> Result<rmce::SecretKey, Error> {
+  match args {
+    [identity] => decode_identity(identity),
+    _ => Err(Error::InvalidEncoding),
+  }
+}
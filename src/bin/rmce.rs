@@ -0,0 +1,437 @@
+use std::{
+  fs,
+  io::{self, Read, Write},
+  path::{Path, PathBuf},
+  process::ExitCode,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::{Parser, Subcommand, ValueEnum};
+use rmce::{
+  asn1::{der::pem, DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding},
+  stream::{Decryptor, Encryptor},
+  PublicKey, SecretKey, ShareableSecret,
+};
+
+type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+/// Classic McEliece (mceliece8192128f) keys, encapsulation and file encryption.
+///
+/// Keys and ciphertexts are read in any of the supported encodings; the
+/// encoding is detected from the contents. In PEM, public keys are X.509
+/// `SubjectPublicKeyInfo` and secret keys PKCS#8 `PrivateKeyInfo`.
+#[derive(Parser)]
+#[command(name = "rmce", version)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Generates a key pair.
+  Keygen {
+    /// Secret key output.
+    #[arg(short, long)]
+    out: PathBuf,
+    /// Public key output.
+    #[arg(short, long)]
+    pubout: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Pem)]
+    format: Format,
+  },
+  /// Derives the public key of a secret key.
+  Pubkey {
+    /// Secret key, stdin if omitted.
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    /// Public key output, stdout if omitted.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Pem)]
+    format: Format,
+  },
+  /// Encapsulates a fresh shared key to a public key and prints the key.
+  Encaps {
+    /// Recipient public key.
+    #[arg(short = 'k', long)]
+    pubkey: PathBuf,
+    /// Ciphertext output.
+    #[arg(short, long)]
+    out: PathBuf,
+    #[arg(short, long, value_enum, default_value_t = Format::Pem)]
+    format: Format,
+    #[arg(long, value_enum, default_value_t = KeyFormat::Hex)]
+    key_format: KeyFormat,
+  },
+  /// Decapsulates a ciphertext and prints the shared key.
+  Decaps {
+    #[arg(short, long)]
+    secret: PathBuf,
+    /// Ciphertext, stdin if omitted.
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = KeyFormat::Hex)]
+    key_format: KeyFormat,
+  },
+  /// Encrypts a file to a public key.
+  Encrypt {
+    /// Recipient public key.
+    #[arg(short, long)]
+    recipient: PathBuf,
+    /// Plaintext, stdin if omitted.
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    /// Ciphertext output, stdout if omitted.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+  },
+  /// Decrypts a file produced by `encrypt`.
+  ///
+  /// An output file only appears once the whole stream is authenticated. On
+  /// stdout, the plaintext of the chunks before a failing one is written out
+  /// before the error.
+  Decrypt {
+    #[arg(short, long)]
+    secret: PathBuf,
+    /// Ciphertext, stdin if omitted.
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    /// Plaintext output, stdout if omitted.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+  },
+  /// Tells what a file contains and whether it is well-formed.
+  Inspect {
+    /// File to inspect, stdin if omitted.
+    input: Option<PathBuf>,
+  },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+  Raw,
+  Hex,
+  Base64,
+  Pem,
+}
+
+/* shared keys are printed, never read back, so there is no PEM label for them */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyFormat {
+  Raw,
+  Hex,
+  Base64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+  PublicKey,
+  SecretKey,
+  Ciphertext,
+}
+
+/* ciphertexts have no standard PEM form; keys use the SPKI and PKCS#8 labels */
+const CIPHERTEXT_LABEL: &str = "MCELIECE8192128F CIPHERTEXT";
+
+impl Kind {
+  const ALL: [Kind; 3] = [Kind::PublicKey, Kind::SecretKey, Kind::Ciphertext];
+  fn size(self) -> usize {
+    match self {
+      Kind::PublicKey => PublicKey::SIZE,
+      Kind::SecretKey => SecretKey::SIZE,
+      Kind::Ciphertext => ShareableSecret::SIZE,
+    }
+  }
+  fn name(self) -> &'static str {
+    match self {
+      Kind::PublicKey => "public key",
+      Kind::SecretKey => "secret key",
+      Kind::Ciphertext => "ciphertext",
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  match run(Cli::parse().command) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("rmce: {err}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(command: Command) -> Result<()> {
+  match command {
+    Command::Keygen {
+      out,
+      pubout,
+      format,
+    } => {
+      let (pk, sk) = rmce::generate_keypair();
+      write_secret(&out, &encode(sk.as_bytes(), Kind::SecretKey, format)?)?;
+      if let Some(pubout) = pubout {
+        write_output(
+          Some(&pubout),
+          &encode(pk.as_bytes(), Kind::PublicKey, format)?,
+        )?;
+      }
+    }
+    Command::Pubkey { input, out, format } => {
+      let sk = read_secret_key(input.as_deref())?;
+      let pk = sk.public_key()?;
+      write_output(
+        out.as_deref(),
+        &encode(pk.as_bytes(), Kind::PublicKey, format)?,
+      )?;
+    }
+    Command::Encaps {
+      pubkey,
+      out,
+      format,
+      key_format,
+    } => {
+      let pk = read_public_key(&pubkey)?;
+      let (ct, key) = pk.encapsulate();
+      write_output(Some(&out), &encode(ct.as_bytes(), Kind::Ciphertext, format)?)?;
+      io::stdout().write_all(&encode_key(key.as_bytes(), key_format))?;
+    }
+    Command::Decaps {
+      secret,
+      input,
+      key_format,
+    } => {
+      let sk = read_secret_key(Some(&secret))?;
+      let ct = decode(&read_input(input.as_deref())?, Kind::Ciphertext)?;
+      let key = ShareableSecret::try_from(&ct[..])?.try_decapsulate(&sk)?;
+      io::stdout().write_all(&encode_key(key.as_bytes(), key_format))?;
+    }
+    Command::Encrypt {
+      recipient,
+      input,
+      out,
+    } => {
+      let pk = read_public_key(&recipient)?;
+      let mut enc = Encryptor::new(&pk, open_output(out.as_deref())?)?;
+      io::copy(&mut open_input(input.as_deref())?, &mut enc)?;
+      enc.finish()?;
+    }
+    Command::Decrypt { secret, input, out } => {
+      let sk = read_secret_key(Some(&secret))?;
+      let mut dec = Decryptor::new_expanded(&sk.expand(), open_input(input.as_deref())?)?;
+      match out.as_deref() {
+        Some(path) if !is_stdio(Some(path)) => write_atomically(path, |out| {
+          io::copy(&mut dec, out)?;
+          Ok(())
+        })?,
+        _ => {
+          let mut out = io::stdout().lock();
+          io::copy(&mut dec, &mut out)?;
+          out.flush()?;
+        }
+      }
+    }
+    Command::Inspect { input } => inspect(&read_input(input.as_deref())?),
+  }
+  Ok(())
+}
+
+fn inspect(data: &[u8]) {
+  for (magic, name) in [
+    (&b"RMCS"[..], "encrypted stream"),
+    (b"RMCE", "sealed message"),
+    (b"RMCM", "multi-recipient envelope"),
+  ] {
+    if data.len() > magic.len() && data.starts_with(magic) {
+      println!("type: {name}");
+      println!("version: {}", data[magic.len()]);
+      println!("size: {} bytes", data.len());
+      return;
+    }
+  }
+
+  for kind in Kind::ALL {
+    let Some((bytes, format)) = decode_as(data, kind) else {
+      continue;
+    };
+    println!("type: {}", kind.name());
+    println!("encoding: {}", format_name(format));
+    println!("size: {} bytes", bytes.len());
+    let valid = match kind {
+      Kind::PublicKey => PublicKey::try_from(bytes).map(drop),
+      Kind::SecretKey => SecretKey::try_from(bytes).and_then(|sk| sk.validate()),
+      Kind::Ciphertext => ShareableSecret::try_from(&bytes[..]).and_then(|ct| ct.validate()),
+    };
+    match valid {
+      Ok(()) => println!("valid: yes"),
+      Err(err) => println!("valid: no ({err})"),
+    }
+    return;
+  }
+  println!("type: unknown");
+  println!("size: {} bytes", data.len());
+}
+
+fn format_name(format: Format) -> &'static str {
+  match format {
+    Format::Raw => "raw",
+    Format::Hex => "hex",
+    Format::Base64 => "base64",
+    Format::Pem => "pem",
+  }
+}
+
+fn encode(bytes: &[u8], kind: Kind, format: Format) -> Result<Vec<u8>> {
+  Ok(match format {
+    Format::Raw => bytes.to_vec(),
+    Format::Hex => format!("{}\n", hex(bytes)).into_bytes(),
+    Format::Base64 => format!("{}\n", BASE64.encode(bytes)).into_bytes(),
+    Format::Pem => match kind {
+      Kind::PublicKey => PublicKey::try_from(bytes)?
+        .to_public_key_pem(LineEnding::LF)?
+        .into_bytes(),
+      Kind::SecretKey => SecretKey::try_from(bytes)?
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|err| err.to_string())?
+        .as_bytes()
+        .to_vec(),
+      Kind::Ciphertext => pem::encode_string(CIPHERTEXT_LABEL, LineEnding::LF, bytes)
+        .map_err(|err| err.to_string())?
+        .into_bytes(),
+    },
+  })
+}
+
+fn encode_key(bytes: &[u8], format: KeyFormat) -> Vec<u8> {
+  match format {
+    KeyFormat::Raw => bytes.to_vec(),
+    KeyFormat::Hex => format!("{}\n", hex(bytes)).into_bytes(),
+    KeyFormat::Base64 => format!("{}\n", BASE64.encode(bytes)).into_bytes(),
+  }
+}
+
+fn decode(data: &[u8], kind: Kind) -> Result<Vec<u8>> {
+  let (bytes, _) = decode_as(data, kind)
+    .ok_or_else(|| format!("input is not a {} in any supported encoding", kind.name()))?;
+  Ok(bytes)
+}
+
+/* raw if the size matches exactly, otherwise text in one of the other encodings */
+fn decode_as(data: &[u8], kind: Kind) -> Option<(Vec<u8>, Format)> {
+  if data.len() == kind.size() {
+    return Some((data.to_vec(), Format::Raw));
+  }
+  let text = std::str::from_utf8(data).ok()?.trim();
+  let (bytes, format) = if text.starts_with("-----BEGIN ") {
+    let bytes = match kind {
+      Kind::PublicKey => PublicKey::from_public_key_pem(text).ok()?.as_bytes().to_vec(),
+      Kind::SecretKey => SecretKey::from_pkcs8_pem(text).ok()?.as_bytes().to_vec(),
+      Kind::Ciphertext => {
+        let (label, bytes) = pem::decode_vec(text.as_bytes()).ok()?;
+        (label == CIPHERTEXT_LABEL).then_some(bytes)?
+      }
+    };
+    (bytes, Format::Pem)
+  } else if text.len() == kind.size() * 2 {
+    (unhex(text)?, Format::Hex)
+  } else {
+    let body: String = text.split_whitespace().collect();
+    (BASE64.decode(body).ok()?, Format::Base64)
+  };
+  (bytes.len() == kind.size()).then_some((bytes, format))
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+  if !text.is_ascii() {
+    return None;
+  }
+  (0..text.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+    .collect()
+}
+
+fn read_public_key(path: &Path) -> Result<PublicKey> {
+  let bytes = decode(&fs::read(path)?, Kind::PublicKey)?;
+  Ok(PublicKey::try_from(bytes)?)
+}
+
+fn read_secret_key(path: Option<&Path>) -> Result<SecretKey> {
+  let bytes = decode(&read_input(path)?, Kind::SecretKey)?;
+  let sk = SecretKey::try_from(bytes)?;
+  sk.validate()?;
+  Ok(sk)
+}
+
+fn is_stdio(path: Option<&Path>) -> bool {
+  path.is_none_or(|p| p == Path::new("-"))
+}
+
+fn read_input(path: Option<&Path>) -> io::Result<Vec<u8>> {
+  let mut data = Vec::new();
+  open_input(path)?.read_to_end(&mut data)?;
+  Ok(data)
+}
+
+fn open_input(path: Option<&Path>) -> io::Result<Box<dyn Read>> {
+  match path {
+    Some(path) if !is_stdio(Some(path)) => Ok(Box::new(io::BufReader::new(fs::File::open(path)?))),
+    _ => Ok(Box::new(io::stdin().lock())),
+  }
+}
+
+fn open_output(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+  match path {
+    Some(path) if !is_stdio(Some(path)) => {
+      Ok(Box::new(io::BufWriter::new(fs::File::create(path)?)))
+    }
+    _ => Ok(Box::new(io::stdout().lock())),
+  }
+}
+
+fn write_output(path: Option<&Path>, data: &[u8]) -> io::Result<()> {
+  let mut out = open_output(path)?;
+  out.write_all(data)?;
+  out.flush()
+}
+
+/* writes next to `path` and renames over it only if `write` succeeds */
+fn write_atomically(
+  path: &Path,
+  write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+  let mut name = path
+    .file_name()
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output is not a file name"))?
+    .to_os_string();
+  name.push(".partial");
+  let tmp = path.with_file_name(name);
+  let result = (|| {
+    let mut file = io::BufWriter::new(fs::File::create(&tmp)?);
+    write(&mut file)?;
+    file.flush()?;
+    fs::rename(&tmp, path)
+  })();
+  if result.is_err() {
+    let _ = fs::remove_file(&tmp);
+  }
+  result
+}
+
+/* secret keys are only readable by the owner */
+fn write_secret(path: &Path, data: &[u8]) -> io::Result<()> {
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+  let mut file = options.open(path)?;
+  /* the mode above only applies to newly created files */
+  #[cfg(unix)]
+  file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+  file.write_all(data)
+}
@@ -0,0 +1,545 @@
+//! CMS (RFC 5652) `EnvelopedData` and `AuthEnvelopedData` for [`PublicKey`]
+//! recipients, using the `KEMRecipientInfo` of RFC 9629.
+//!
+//! Every recipient gets an `OtherRecipientInfo` of type `id-ori-kem` holding a
+//! [`KemRecipientInfo`]: `kemct` is the [`ShareableSecret`] of an encapsulation
+//! to the recipient's key, and the content-encryption key is wrapped with
+//! AES-256 key wrap (RFC 3394) under a key-encryption key derived from the
+//! [`SharedKey`] with HKDF-SHA256 (RFC 8619). As RFC 9629 specifies, the HKDF
+//! salt is empty and the info is the DER encoding of `CMSORIforKEMOtherInfo`.
+//!
+//! [`envelope`] encrypts the content with AES-256-CBC into `EnvelopedData`,
+//! [`auth_envelope`] with AES-256-GCM (RFC 5084) into `AuthEnvelopedData`
+//! (RFC 5083). [`decrypt`] opens both, for the recipient named by a
+//! `RecipientIdentifier`, which is usually taken from the recipient's
+//! certificate (see [`crate::x509`]).
+
+use aes::Aes256;
+use aes_gcm::{
+  aead::{AeadInPlace, KeyInit},
+  Aes256Gcm,
+};
+use aes_kw::KekAes256;
+use cbc::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+pub use cms::{
+  self,
+  cert::{x509::attr::Attributes, IssuerAndSerialNumber},
+  content_info::{CmsVersion, ContentInfo},
+  enveloped_data::{
+    EncryptedContentInfo, EnvelopedData, OriginatorInfo, OtherRecipientInfo, RecipientIdentifier,
+    RecipientInfo, RecipientInfos,
+  },
+};
+use der::{
+  asn1::{ObjectIdentifier, OctetString, SetOfVec},
+  Any, Choice, Decode, DecodeValue, Encode, EncodeValue, Sequence, Tagged,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{
+  asn1::{spki::AlgorithmIdentifierOwned, OID},
+  Error, ExpandedSecretKey, PublicKey, SecretKey, ShareableSecret, SharedKey,
+};
+
+/// `id-data`, the content type of the encrypted content.
+pub const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+/// `id-envelopedData`
+pub const ID_ENVELOPED_DATA: ObjectIdentifier =
+  ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.3");
+/// `id-ct-authEnvelopedData`
+pub const ID_CT_AUTH_ENVELOPED_DATA: ObjectIdentifier =
+  ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.23");
+/// `id-ori-kem`, the `oriType` of a [`KemRecipientInfo`].
+pub const ID_ORI_KEM: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.13.3");
+/// `id-alg-hkdf-with-sha256`
+pub const ID_ALG_HKDF_WITH_SHA256: ObjectIdentifier =
+  ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.3.28");
+/// `id-aes256-wrap`
+pub const ID_AES256_WRAP: ObjectIdentifier =
+  ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.45");
+/// `id-aes256-CBC`
+pub const ID_AES256_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.42");
+/// `id-aes256-GCM`
+pub const ID_AES256_GCM: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.1.46");
+
+const KEY_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const WRAP_OVERHEAD: usize = 8;
+
+/// ```text
+/// KEMRecipientInfo ::= SEQUENCE {
+///   version CMSVersion,  -- always set to 0
+///   rid RecipientIdentifier,
+///   kem KEMAlgorithmIdentifier,
+///   kemct OCTET STRING,
+///   kdf KeyDerivationAlgorithmIdentifier,
+///   kekLength INTEGER (1..65535),
+///   ukm [0] EXPLICIT UserKeyingMaterial OPTIONAL,
+///   wrap KeyEncryptionAlgorithmIdentifier,
+///   encryptedKey EncryptedKey }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct KemRecipientInfo {
+  pub version: CmsVersion,
+  pub rid: RecipientIdentifier,
+  pub kem: AlgorithmIdentifierOwned,
+  pub kemct: OctetString,
+  pub kdf: AlgorithmIdentifierOwned,
+  pub kek_length: u16,
+  #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+  pub ukm: Option<OctetString>,
+  pub wrap: AlgorithmIdentifierOwned,
+  pub encrypted_key: OctetString,
+}
+
+/// ```text
+/// CMSORIforKEMOtherInfo ::= SEQUENCE {
+///   wrap KeyEncryptionAlgorithmIdentifier,
+///   kekLength INTEGER (1..65535),
+///   ukm [0] EXPLICIT UserKeyingMaterial OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct CmsOriForKemOtherInfo {
+  pub wrap: AlgorithmIdentifierOwned,
+  pub kek_length: u16,
+  #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+  pub ukm: Option<OctetString>,
+}
+
+/// ```text
+/// AuthEnvelopedData ::= SEQUENCE {
+///   version CMSVersion,
+///   originatorInfo [0] IMPLICIT OriginatorInfo OPTIONAL,
+///   recipientInfos RecipientInfos,
+///   authEncryptedContentInfo EncryptedContentInfo,
+///   authAttrs [1] IMPLICIT AuthAttributes OPTIONAL,
+///   mac MessageAuthenticationCode,
+///   unauthAttrs [2] IMPLICIT UnauthAttributes OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct AuthEnvelopedData {
+  pub version: CmsVersion,
+  #[asn1(
+    context_specific = "0",
+    tag_mode = "IMPLICIT",
+    constructed = "true",
+    optional = "true"
+  )]
+  pub originator_info: Option<OriginatorInfo>,
+  pub recip_infos: RecipientInfos,
+  pub auth_encrypted_content: EncryptedContentInfo,
+  #[asn1(
+    context_specific = "1",
+    tag_mode = "IMPLICIT",
+    constructed = "true",
+    optional = "true"
+  )]
+  pub auth_attrs: Option<Attributes>,
+  pub mac: OctetString,
+  #[asn1(
+    context_specific = "2",
+    tag_mode = "IMPLICIT",
+    constructed = "true",
+    optional = "true"
+  )]
+  pub unauth_attrs: Option<Attributes>,
+}
+
+/// ```text
+/// GCMParameters ::= SEQUENCE {
+///   aes-nonce OCTET STRING,
+///   aes-ICVlen AES-GCM-ICVlen DEFAULT 12 }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct GcmParameters {
+  pub nonce: OctetString,
+  #[asn1(default = "default_icv_len")]
+  pub icv_len: u8,
+}
+
+fn default_icv_len() -> u8 {
+  12
+}
+
+/// A recipient's key and the identifier its `KEMRecipientInfo` is labeled with.
+#[derive(Debug, Clone)]
+pub struct Recipient<'a> {
+  pub public_key: &'a PublicKey,
+  pub rid: RecipientIdentifier,
+}
+
+fn algorithm(oid: ObjectIdentifier) -> AlgorithmIdentifierOwned {
+  AlgorithmIdentifierOwned {
+    oid,
+    parameters: None,
+  }
+}
+
+fn algorithm_with<P: EncodeValue + Tagged>(
+  oid: ObjectIdentifier,
+  parameters: &P,
+) -> Result<AlgorithmIdentifierOwned, Error> {
+  Ok(AlgorithmIdentifierOwned {
+    oid,
+    parameters: Some(Any::encode_from(parameters).map_err(|_| Error::InvalidEncoding)?),
+  })
+}
+
+fn octet_string(bytes: &[u8]) -> Result<OctetString, Error> {
+  OctetString::new(bytes).map_err(|_| Error::InvalidEncoding)
+}
+
+/* RFC 9629 section 5: HKDF over the shared secret with the DER of
+ * CMSORIforKEMOtherInfo as info */
+fn key_encryption_key(
+  key: &SharedKey,
+  wrap: &AlgorithmIdentifierOwned,
+  kek_length: u16,
+  ukm: Option<&OctetString>,
+) -> Result<KekAes256, Error> {
+  let info = CmsOriForKemOtherInfo {
+    wrap: wrap.clone(),
+    kek_length,
+    ukm: ukm.cloned(),
+  }
+  .to_der()
+  .map_err(|_| Error::InvalidEncoding)?;
+  let mut kek = Zeroizing::new([0u8; KEY_LEN]);
+  Hkdf::<Sha256>::new(None, key.as_bytes())
+    .expand(&info, kek.as_mut())
+    .unwrap();
+  Ok(KekAes256::new((&*kek).into()))
+}
+
+/// Encapsulates to `pk` and wraps `content_key` for it. `content_key` must be
+/// a multiple of 8 bytes long and at least 16 bytes.
+#[cfg(feature = "openssl")]
+pub fn recipient_info(
+  pk: &PublicKey,
+  rid: RecipientIdentifier,
+  content_key: &[u8],
+  ukm: Option<&[u8]>,
+) -> Result<RecipientInfo, Error> {
+  recipient_info_with_entropy_provider(pk, rid, content_key, ukm, crate::crypto_random)
+}
+
+pub fn recipient_info_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  rid: RecipientIdentifier,
+  content_key: &[u8],
+  ukm: Option<&[u8]>,
+  entropy_provider: F,
+) -> Result<RecipientInfo, Error> {
+  if content_key.len() < 16 || !content_key.len().is_multiple_of(8) {
+    return Err(Error::InvalidLength {
+      got: content_key.len(),
+      expected: KEY_LEN,
+    });
+  }
+  let ukm = ukm.map(octet_string).transpose()?;
+  let wrap = algorithm(ID_AES256_WRAP);
+  let (enc, key) = pk.encapsulate_with_entropy_provider(entropy_provider);
+  let mut wrapped = vec![0u8; content_key.len() + WRAP_OVERHEAD];
+  key_encryption_key(&key, &wrap, KEY_LEN as u16, ukm.as_ref())?
+    .wrap(content_key, &mut wrapped)
+    .unwrap();
+  let info = KemRecipientInfo {
+    version: CmsVersion::V0,
+    rid,
+    kem: algorithm(OID),
+    kemct: octet_string(enc.as_bytes())?,
+    kdf: algorithm(ID_ALG_HKDF_WITH_SHA256),
+    kek_length: KEY_LEN as u16,
+    ukm,
+    wrap,
+    encrypted_key: octet_string(&wrapped)?,
+  };
+  Ok(RecipientInfo::Ori(OtherRecipientInfo {
+    ori_type: ID_ORI_KEM,
+    ori_value: Any::encode_from(&info).map_err(|_| Error::InvalidEncoding)?,
+  }))
+}
+
+/// Decapsulates `info.kemct` and unwraps the content-encryption key. Only
+/// HKDF-SHA256 and AES-256 key wrap are supported, anything else fails with
+/// [`Error::AlgorithmMismatch`].
+pub fn unwrap_content_key(
+  sk: &ExpandedSecretKey,
+  info: &KemRecipientInfo,
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+  if info.kem != algorithm(OID)
+    || info.kdf != algorithm(ID_ALG_HKDF_WITH_SHA256)
+    || info.wrap != algorithm(ID_AES256_WRAP)
+    || info.kek_length as usize != KEY_LEN
+  {
+    return Err(Error::AlgorithmMismatch);
+  }
+  let enc = ShareableSecret::try_from(info.kemct.as_bytes())?;
+  let key = enc.try_decapsulate_expanded(sk)?;
+  let wrapped = info.encrypted_key.as_bytes();
+  if wrapped.len() < 16 + WRAP_OVERHEAD {
+    return Err(Error::DecryptionFailed);
+  }
+  let mut content_key = Zeroizing::new(vec![0u8; wrapped.len() - WRAP_OVERHEAD]);
+  key_encryption_key(&key, &info.wrap, info.kek_length, info.ukm.as_ref())?
+    .unwrap(wrapped, &mut content_key)
+    .map_err(|_| Error::DecryptionFailed)?;
+  Ok(content_key)
+}
+
+fn recipient_infos<F: FnMut(&mut [u8])>(
+  recipients: &[Recipient<'_>],
+  content_key: &[u8],
+  mut entropy_provider: F,
+) -> Result<RecipientInfos, Error> {
+  /* RecipientInfos is SET SIZE (1..MAX) */
+  if recipients.is_empty() {
+    return Err(Error::InvalidEncoding);
+  }
+  let infos = recipients
+    .iter()
+    .map(|recipient| {
+      recipient_info_with_entropy_provider(
+        recipient.public_key,
+        recipient.rid.clone(),
+        content_key,
+        None,
+        &mut entropy_provider,
+      )
+    })
+    .collect::<Result<Vec<_>, Error>>()?;
+  SetOfVec::try_from(infos)
+    .map(RecipientInfos)
+    .map_err(|_| Error::InvalidEncoding)
+}
+
+fn content_info<T: EncodeValue + Tagged>(
+  content_type: ObjectIdentifier,
+  content: &T,
+) -> Result<ContentInfo, Error> {
+  Ok(ContentInfo {
+    content_type,
+    content: Any::encode_from(content).map_err(|_| Error::InvalidEncoding)?,
+  })
+}
+
+/// Encrypts `content` with AES-256-CBC into `EnvelopedData`. Fails with
+/// [`Error::InvalidEncoding`] without recipients, as `EnvelopedData` cannot
+/// encode an empty `RecipientInfos`.
+#[cfg(feature = "openssl")]
+pub fn envelope(recipients: &[Recipient<'_>], content: &[u8]) -> Result<ContentInfo, Error> {
+  envelope_with_entropy_provider(recipients, content, crate::crypto_random)
+}
+
+pub fn envelope_with_entropy_provider<F: FnMut(&mut [u8])>(
+  recipients: &[Recipient<'_>],
+  content: &[u8],
+  mut entropy_provider: F,
+) -> Result<ContentInfo, Error> {
+  let mut content_key = Zeroizing::new([0u8; KEY_LEN]);
+  entropy_provider(content_key.as_mut());
+  let mut iv = [0u8; IV_LEN];
+  entropy_provider(&mut iv);
+  let recip_infos = recipient_infos(recipients, content_key.as_ref(), entropy_provider)?;
+
+  let ciphertext = cbc::Encryptor::<Aes256>::new((&*content_key).into(), &iv.into())
+    .encrypt_padded_vec_mut::<Pkcs7>(content);
+  let data = EnvelopedData {
+    /* version 3 because of the ori recipient infos */
+    version: CmsVersion::V3,
+    originator_info: None,
+    recip_infos,
+    encrypted_content: EncryptedContentInfo {
+      content_type: ID_DATA,
+      content_enc_alg: algorithm_with(ID_AES256_CBC, &octet_string(&iv)?)?,
+      encrypted_content: Some(octet_string(&ciphertext)?),
+    },
+    unprotected_attrs: None,
+  };
+  content_info(ID_ENVELOPED_DATA, &data)
+}
+
+/// Encrypts `content` with AES-256-GCM into `AuthEnvelopedData`.
+#[cfg(feature = "openssl")]
+pub fn auth_envelope(recipients: &[Recipient<'_>], content: &[u8]) -> Result<ContentInfo, Error> {
+  auth_envelope_with_entropy_provider(recipients, content, crate::crypto_random)
+}
+
+pub fn auth_envelope_with_entropy_provider<F: FnMut(&mut [u8])>(
+  recipients: &[Recipient<'_>],
+  content: &[u8],
+  mut entropy_provider: F,
+) -> Result<ContentInfo, Error> {
+  let mut content_key = Zeroizing::new([0u8; KEY_LEN]);
+  entropy_provider(content_key.as_mut());
+  let mut nonce = [0u8; NONCE_LEN];
+  entropy_provider(&mut nonce);
+  let recip_infos = recipient_infos(recipients, content_key.as_ref(), entropy_provider)?;
+
+  let mut ciphertext = content.to_vec();
+  let tag = Aes256Gcm::new((&*content_key).into())
+    .encrypt_in_place_detached(&nonce.into(), &[], &mut ciphertext)
+    .unwrap();
+  let parameters = GcmParameters {
+    nonce: octet_string(&nonce)?,
+    icv_len: TAG_LEN as u8,
+  };
+  let data = AuthEnvelopedData {
+    version: CmsVersion::V0,
+    originator_info: None,
+    recip_infos,
+    auth_encrypted_content: EncryptedContentInfo {
+      content_type: ID_DATA,
+      content_enc_alg: algorithm_with(ID_AES256_GCM, &parameters)?,
+      encrypted_content: Some(octet_string(&ciphertext)?),
+    },
+    auth_attrs: None,
+    mac: octet_string(&tag)?,
+    unauth_attrs: None,
+  };
+  content_info(ID_CT_AUTH_ENVELOPED_DATA, &data)
+}
+
+/// Decrypts `EnvelopedData` or `AuthEnvelopedData` with the key of the
+/// `KEMRecipientInfo` labeled `rid`. Fails with [`Error::DecryptionFailed`]
+/// when there is no such recipient info.
+pub fn decrypt(
+  sk: &SecretKey,
+  rid: &RecipientIdentifier,
+  content_info: &ContentInfo,
+) -> Result<Vec<u8>, Error> {
+  decrypt_expanded(&sk.expand(), rid, content_info)
+}
+
+pub fn decrypt_expanded(
+  sk: &ExpandedSecretKey,
+  rid: &RecipientIdentifier,
+  content_info: &ContentInfo,
+) -> Result<Vec<u8>, Error> {
+  if content_info.content_type == ID_ENVELOPED_DATA {
+    let data: EnvelopedData = content_info
+      .content
+      .decode_as()
+      .map_err(|_| Error::InvalidEncoding)?;
+    let content_key = content_key(sk, rid, &data.recip_infos)?;
+    decrypt_cbc(&content_key, &data.encrypted_content)
+  } else if content_info.content_type == ID_CT_AUTH_ENVELOPED_DATA {
+    let data: AuthEnvelopedData = content_info
+      .content
+      .decode_as()
+      .map_err(|_| Error::InvalidEncoding)?;
+    let content_key = content_key(sk, rid, &data.recip_infos)?;
+    /* RFC 5083 section 2.2: the authenticated attributes are the AAD */
+    let aad = match &data.auth_attrs {
+      Some(attrs) => attrs.to_der().map_err(|_| Error::InvalidEncoding)?,
+      None => Vec::new(),
+    };
+    decrypt_gcm(
+      &content_key,
+      &data.auth_encrypted_content,
+      &aad,
+      data.mac.as_bytes(),
+    )
+  } else {
+    Err(Error::InvalidHeader)
+  }
+}
+
+/// [`decrypt`] of a DER-encoded `ContentInfo`.
+pub fn decrypt_der(
+  sk: &SecretKey,
+  rid: &RecipientIdentifier,
+  der: &[u8],
+) -> Result<Vec<u8>, Error> {
+  decrypt(
+    sk,
+    rid,
+    &ContentInfo::from_der(der).map_err(|_| Error::InvalidEncoding)?,
+  )
+}
+
+fn content_key(
+  sk: &ExpandedSecretKey,
+  rid: &RecipientIdentifier,
+  recip_infos: &RecipientInfos,
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+  let info = recip_infos
+    .0
+    .iter()
+    .find_map(|info| match info {
+      RecipientInfo::Ori(ori) if ori.ori_type == ID_ORI_KEM => ori
+        .ori_value
+        .decode_as::<KemRecipientInfo>()
+        .ok()
+        .filter(|info| info.rid == *rid),
+      _ => None,
+    })
+    .ok_or(Error::DecryptionFailed)?;
+  unwrap_content_key(sk, &info)
+}
+
+fn encrypted_content(info: &EncryptedContentInfo) -> Result<&[u8], Error> {
+  /* detached content is not supported */
+  info
+    .encrypted_content
+    .as_ref()
+    .map(OctetString::as_bytes)
+    .ok_or(Error::InvalidHeader)
+}
+
+fn parameters<'a, T: Choice<'a> + DecodeValue<'a>>(
+  info: &'a EncryptedContentInfo,
+) -> Result<T, Error> {
+  info
+    .content_enc_alg
+    .parameters
+    .as_ref()
+    .ok_or(Error::InvalidEncoding)?
+    .decode_as()
+    .map_err(|_| Error::InvalidEncoding)
+}
+
+fn decrypt_cbc(content_key: &[u8], info: &EncryptedContentInfo) -> Result<Vec<u8>, Error> {
+  if info.content_enc_alg.oid != ID_AES256_CBC {
+    return Err(Error::AlgorithmMismatch);
+  }
+  let iv: OctetString = parameters(info)?;
+  let decryptor = cbc::Decryptor::<Aes256>::new_from_slices(content_key, iv.as_bytes())
+    .map_err(|_| Error::DecryptionFailed)?;
+  decryptor
+    .decrypt_padded_vec_mut::<Pkcs7>(encrypted_content(info)?)
+    .map_err(|_| Error::DecryptionFailed)
+}
+
+fn decrypt_gcm(
+  content_key: &[u8],
+  info: &EncryptedContentInfo,
+  aad: &[u8],
+  mac: &[u8],
+) -> Result<Vec<u8>, Error> {
+  if info.content_enc_alg.oid != ID_AES256_GCM {
+    return Err(Error::AlgorithmMismatch);
+  }
+  let parameters: GcmParameters = parameters(info)?;
+  if parameters.nonce.as_bytes().len() != NONCE_LEN || parameters.icv_len as usize != TAG_LEN {
+    return Err(Error::AlgorithmMismatch);
+  }
+  if mac.len() != TAG_LEN {
+    return Err(Error::DecryptionFailed);
+  }
+  let cipher = Aes256Gcm::new_from_slice(content_key).map_err(|_| Error::DecryptionFailed)?;
+  let mut content = encrypted_content(info)?.to_vec();
+  cipher
+    .decrypt_in_place_detached(
+      parameters.nonce.as_bytes().into(),
+      aad,
+      &mut content,
+      mac.into(),
+    )
+    .map_err(|_| Error::DecryptionFailed)?;
+  Ok(content)
+}
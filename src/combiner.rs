@@ -0,0 +1,420 @@
+// Shared implementation of the hybrid KEMs in `hybrid` and `hybrid_ml_kem`.
+//
+// Every hybrid pairs a McEliece key with the key of a second KEM and derives
+// the shared key as
+//
+//   SHA3-256(LABEL || ss_M || ss_2 || ct_M || ct_2 || SHA3-256(pk_M) || pk_2)
+//
+// `Component` describes the second KEM; `hybrid_kem!` generates the key,
+// ciphertext and key generation items around it.
+
+/// The second KEM of a hybrid. Byte slices passed in have the lengths given by
+/// the constants.
+pub(crate) trait Component {
+  type PublicKey;
+  type SecretKey;
+  const PUBLIC_KEY_LEN: usize;
+  const SECRET_KEY_LEN: usize;
+  const CIPHERTEXT_LEN: usize;
+  fn public_key_bytes(pk: &Self::PublicKey) -> Vec<u8>;
+  fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, crate::Error>;
+  fn secret_key_bytes(sk: &Self::SecretKey) -> zeroize::Zeroizing<Vec<u8>>;
+  fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, crate::Error>;
+  fn public_key(sk: &Self::SecretKey) -> Self::PublicKey;
+  fn generate<F: FnMut(&mut [u8])>(entropy_provider: F) -> (Self::PublicKey, Self::SecretKey);
+  /// Writes the ciphertext to `ct` and returns the shared secret.
+  fn encapsulate<F: FnMut(&mut [u8])>(
+    pk: &Self::PublicKey,
+    ct: &mut [u8],
+    entropy_provider: F,
+  ) -> zeroize::Zeroizing<Vec<u8>>;
+  fn decapsulate(sk: &Self::SecretKey, ct: &[u8]) -> zeroize::Zeroizing<Vec<u8>>;
+}
+
+/// `bytes` as an array, failing with [`crate::Error::InvalidLength`] instead of
+/// panicking.
+pub(crate) fn array<T, const N: usize>(bytes: &[T]) -> Result<[T; N], crate::Error>
+where
+  T: Copy,
+{
+  bytes.try_into().map_err(|_| crate::Error::InvalidLength {
+    got: bytes.len(),
+    expected: N,
+  })
+}
+
+/// Generates `PublicKey`, `SecretKey`, `ShareableSecret` and the key generation
+/// functions of a hybrid in the calling module. `$name` is the accessor of the
+/// second component, `$pk` and `$sk` are its key types.
+macro_rules! hybrid_kem {
+  (
+    component: $component:ty,
+    name: $name:ident,
+    public_key: $pk:ty,
+    secret_key: $sk:ty,
+    label: $label:expr $(,)?
+  ) => {
+    type Second = $component;
+
+    const LABEL: &[u8] = $label;
+    const DIGEST_LEN: usize = 32;
+    const PK_LEN: usize = <Second as $crate::combiner::Component>::PUBLIC_KEY_LEN;
+    const SK_LEN: usize = <Second as $crate::combiner::Component>::SECRET_KEY_LEN;
+    const CT_LEN: usize = <Second as $crate::combiner::Component>::CIPHERTEXT_LEN;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct PublicKey {
+      mceliece: $crate::PublicKey,
+      $name: $pk,
+      digest: [u8; DIGEST_LEN],
+    }
+
+    impl Eq for PublicKey {}
+
+    impl PublicKey {
+      pub const SIZE: usize = $crate::PublicKey::SIZE + PK_LEN;
+      pub fn new(mceliece: $crate::PublicKey, $name: $pk) -> Self {
+        let mut digest = [0u8; DIGEST_LEN];
+        $crate::impls::libkeccak::sha3_256(&mut digest, &[mceliece.as_bytes()]);
+        Self {
+          mceliece,
+          $name,
+          digest,
+        }
+      }
+      pub fn mceliece(&self) -> &$crate::PublicKey {
+        &self.mceliece
+      }
+      pub fn $name(&self) -> &$pk {
+        &self.$name
+      }
+      pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(self.mceliece.as_bytes());
+        bytes.extend_from_slice(&<Second as $crate::combiner::Component>::public_key_bytes(
+          &self.$name,
+        ));
+        bytes
+      }
+      #[cfg(feature = "openssl")]
+      pub fn encapsulate(&self) -> (ShareableSecret, $crate::SharedKey) {
+        self.encapsulate_with_entropy_provider($crate::crypto_random)
+      }
+      pub fn encapsulate_with_entropy_provider<F: FnMut(&mut [u8])>(
+        &self,
+        mut entropy_provider: F,
+      ) -> (ShareableSecret, $crate::SharedKey) {
+        let (mceliece, ss_m) = self
+          .mceliece
+          .encapsulate_with_entropy_provider(&mut entropy_provider);
+        let mut ct = ShareableSecret {
+          mceliece,
+          $name: [0u8; CT_LEN],
+        };
+        let ss_2 = <Second as $crate::combiner::Component>::encapsulate(
+          &self.$name,
+          &mut ct.$name,
+          entropy_provider,
+        );
+        let key = combine(&ss_m, &ss_2, &ct, &self.digest, &self.$name);
+        (ct, key)
+      }
+    }
+
+    impl TryFrom<&[u8]> for PublicKey {
+      type Error = $crate::Error;
+      fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != Self::SIZE {
+          return Err($crate::Error::InvalidLength {
+            got: value.len(),
+            expected: Self::SIZE,
+          });
+        }
+        let (mceliece, second) = value.split_at($crate::PublicKey::SIZE);
+        Ok(Self::new(
+          mceliece.try_into()?,
+          <Second as $crate::combiner::Component>::public_key_from_bytes(second)?,
+        ))
+      }
+    }
+
+    impl $crate::encoding::ByteRepr for PublicKey {
+      fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+        self.to_bytes()
+      }
+      fn from_repr_bytes(bytes: &[u8]) -> Result<Self, $crate::Error> {
+        Self::try_from(bytes)
+      }
+    }
+
+    impl serde::Serialize for PublicKey {
+      fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+      where
+        S: serde::Serializer,
+      {
+        $crate::encoding::base64::serialize(self, serializer)
+      }
+    }
+
+    impl<'de> serde::Deserialize<'de> for PublicKey {
+      fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+      where
+        D: serde::Deserializer<'de>,
+      {
+        $crate::encoding::base64::deserialize(deserializer)
+      }
+    }
+
+    /// Encoded as the McEliece secret key, the secret key of the second
+    /// component and the digest of the McEliece public key.
+    #[derive(Clone)]
+    pub struct SecretKey {
+      mceliece: $crate::SecretKey,
+      $name: $sk,
+      digest: [u8; DIGEST_LEN],
+    }
+
+    impl std::fmt::Debug for SecretKey {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+      }
+    }
+
+    impl PartialEq for SecretKey {
+      fn eq(&self, other: &Self) -> bool {
+        self.mceliece == other.mceliece
+          && <Second as $crate::combiner::Component>::secret_key_bytes(&self.$name)
+            == <Second as $crate::combiner::Component>::secret_key_bytes(&other.$name)
+          && self.digest == other.digest
+      }
+    }
+
+    impl Eq for SecretKey {}
+
+    impl SecretKey {
+      pub const SIZE: usize = $crate::SecretKey::SIZE + SK_LEN + DIGEST_LEN;
+      /// Recomputes the McEliece public key to bind it into the shared key, so
+      /// this is as slow as key generation.
+      pub fn new(mceliece: $crate::SecretKey, $name: $sk) -> Result<Self, $crate::Error> {
+        let mut digest = [0u8; DIGEST_LEN];
+        $crate::impls::libkeccak::sha3_256(&mut digest, &[mceliece.public_key()?.as_bytes()]);
+        Ok(Self {
+          mceliece,
+          $name,
+          digest,
+        })
+      }
+      pub fn mceliece(&self) -> &$crate::SecretKey {
+        &self.mceliece
+      }
+      pub fn $name(&self) -> &$sk {
+        &self.$name
+      }
+      pub fn to_bytes(&self) -> zeroize::Zeroizing<Vec<u8>> {
+        let mut bytes = zeroize::Zeroizing::new(Vec::with_capacity(Self::SIZE));
+        bytes.extend_from_slice(self.mceliece.as_bytes());
+        bytes.extend_from_slice(&<Second as $crate::combiner::Component>::secret_key_bytes(
+          &self.$name,
+        ));
+        bytes.extend_from_slice(&self.digest);
+        bytes
+      }
+      pub fn public_key(&self) -> Result<PublicKey, $crate::Error> {
+        let mceliece = self.mceliece.public_key()?;
+        Ok(PublicKey::new(
+          mceliece,
+          <Second as $crate::combiner::Component>::public_key(&self.$name),
+        ))
+      }
+      /// See [`crate::SecretKey::validate`].
+      pub fn validate(&self) -> Result<(), $crate::Error> {
+        self.mceliece.validate()
+      }
+    }
+
+    /// Recomputes the digest of the McEliece public key, as [`SecretKey::new`]
+    /// does, and fails with [`crate::Error::InvalidSecretKey`] when the stored
+    /// one differs, so decoding is as slow as key generation.
+    impl TryFrom<&[u8]> for SecretKey {
+      type Error = $crate::Error;
+      fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != Self::SIZE {
+          return Err($crate::Error::InvalidLength {
+            got: value.len(),
+            expected: Self::SIZE,
+          });
+        }
+        let (mceliece, rest) = value.split_at($crate::SecretKey::SIZE);
+        let (second, digest) = rest.split_at(SK_LEN);
+        let sk = Self::new(
+          mceliece.try_into()?,
+          <Second as $crate::combiner::Component>::secret_key_from_bytes(second)?,
+        )?;
+        if sk.digest[..] != *digest {
+          return Err($crate::Error::InvalidSecretKey);
+        }
+        Ok(sk)
+      }
+    }
+
+    impl $crate::encoding::ByteRepr for SecretKey {
+      fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+        self.to_bytes()
+      }
+      fn from_repr_bytes(bytes: &[u8]) -> Result<Self, $crate::Error> {
+        Self::try_from(bytes)
+      }
+    }
+
+    impl serde::Serialize for SecretKey {
+      fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+      where
+        S: serde::Serializer,
+      {
+        $crate::encoding::base64::serialize(self, serializer)
+      }
+    }
+
+    impl<'de> serde::Deserialize<'de> for SecretKey {
+      fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+      where
+        D: serde::Deserializer<'de>,
+      {
+        $crate::encoding::base64::deserialize(deserializer)
+      }
+    }
+
+    #[cfg(feature = "openssl")]
+    pub fn generate_keypair() -> (PublicKey, SecretKey) {
+      generate_keypair_with_entropy_provider($crate::crypto_random)
+    }
+
+    pub fn generate_keypair_with_entropy_provider<F: FnMut(&mut [u8])>(
+      mut entropy_provider: F,
+    ) -> (PublicKey, SecretKey) {
+      let (pk_m, sk_m) = $crate::generate_keypair_with_entropy_provider(&mut entropy_provider);
+      let (pk_2, sk_2) = <Second as $crate::combiner::Component>::generate(entropy_provider);
+
+      let pk = PublicKey::new(pk_m, pk_2);
+      let sk = SecretKey {
+        mceliece: sk_m,
+        $name: sk_2,
+        digest: pk.digest,
+      };
+      (pk, sk)
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ShareableSecret {
+      mceliece: $crate::ShareableSecret,
+      $name: [u8; CT_LEN],
+    }
+
+    impl ShareableSecret {
+      pub const SIZE: usize = $crate::ShareableSecret::SIZE + CT_LEN;
+      pub fn mceliece(&self) -> &$crate::ShareableSecret {
+        &self.mceliece
+      }
+      pub fn $name(&self) -> &[u8; CT_LEN] {
+        &self.$name
+      }
+      pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        let (mceliece, second) = bytes.split_at_mut($crate::ShareableSecret::SIZE);
+        mceliece.copy_from_slice(self.mceliece.as_bytes());
+        second.copy_from_slice(&self.$name);
+        bytes
+      }
+      pub fn decapsulate(&self, sk: &SecretKey) -> $crate::SharedKey {
+        let ss_m = self.mceliece.decapsulate(&sk.mceliece);
+        let ss_2 = <Second as $crate::combiner::Component>::decapsulate(&sk.$name, &self.$name);
+        let pk_2 = <Second as $crate::combiner::Component>::public_key(&sk.$name);
+        combine(&ss_m, &ss_2, self, &sk.digest, &pk_2)
+      }
+      /// See [`crate::ShareableSecret::try_decapsulate`].
+      pub fn try_decapsulate(&self, sk: &SecretKey) -> Result<$crate::SharedKey, $crate::Error> {
+        self.validate()?;
+        Ok(self.decapsulate(sk))
+      }
+      /// See [`crate::ShareableSecret::validate`].
+      pub fn validate(&self) -> Result<(), $crate::Error> {
+        self.mceliece.validate()
+      }
+    }
+
+    impl TryFrom<&[u8]> for ShareableSecret {
+      type Error = $crate::Error;
+      fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != Self::SIZE {
+          return Err($crate::Error::InvalidLength {
+            got: value.len(),
+            expected: Self::SIZE,
+          });
+        }
+        let (mceliece, second) = value.split_at($crate::ShareableSecret::SIZE);
+        Ok(Self {
+          mceliece: mceliece.try_into()?,
+          $name: $crate::combiner::array(second)?,
+        })
+      }
+    }
+
+    impl From<ShareableSecret> for [u8; ShareableSecret::SIZE] {
+      fn from(s: ShareableSecret) -> Self {
+        s.to_bytes()
+      }
+    }
+
+    impl $crate::encoding::ByteRepr for ShareableSecret {
+      fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+        self.to_bytes()
+      }
+      fn from_repr_bytes(bytes: &[u8]) -> Result<Self, $crate::Error> {
+        Self::try_from(bytes)
+      }
+    }
+
+    impl serde::Serialize for ShareableSecret {
+      fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+      where
+        S: serde::Serializer,
+      {
+        $crate::encoding::base64::serialize(self, serializer)
+      }
+    }
+
+    impl<'de> serde::Deserialize<'de> for ShareableSecret {
+      fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+      where
+        D: serde::Deserializer<'de>,
+      {
+        $crate::encoding::base64::deserialize(deserializer)
+      }
+    }
+
+    fn combine(
+      ss_m: &$crate::SharedKey,
+      ss_2: &[u8],
+      ct: &ShareableSecret,
+      pk_m_digest: &[u8; DIGEST_LEN],
+      pk_2: &$pk,
+    ) -> $crate::SharedKey {
+      let mut key = $crate::SharedKey([0u8; $crate::SharedKey::SIZE]);
+      $crate::impls::libkeccak::sha3_256(
+        &mut key.0,
+        &[
+          LABEL,
+          ss_m.as_bytes(),
+          ss_2,
+          ct.mceliece.as_bytes(),
+          &ct.$name,
+          pk_m_digest,
+          &<Second as $crate::combiner::Component>::public_key_bytes(pk_2),
+        ],
+      );
+      key
+    }
+  };
+}
+
+pub(crate) use hybrid_kem;
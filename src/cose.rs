@@ -0,0 +1,308 @@
+//! COSE_Key (RFC 9052) encodings of keys, and COSE_Encrypt messages with
+//! Classic McEliece as the key establishment algorithm.
+//!
+//! Keys are COSE_Keys of type `AKP` (7): `pub` (-1) and `priv` (-2) hold the
+//! bytes of [`PublicKey::as_bytes`] and [`SecretKey::as_bytes`], and `alg` is a
+//! value of the private use range standing for the parameter set (see
+//! [`ParameterSet::cose_alg`]), as no values are registered with IANA yet.
+//!
+//! # COSE_Encrypt
+//!
+//! [`encrypt`] produces a tagged COSE_Encrypt of AES-256-GCM content with the
+//! IV in the unprotected header, and a single recipient that encapsulates to
+//! the public key: its protected header holds the `alg` of the parameter set
+//! and its ciphertext is the [`ShareableSecret`]. No key is wrapped: the
+//! content encryption key is HKDF-SHA256 of the encapsulated [`SharedKey`]
+//! with an empty salt and, as info, the COSE_KDF_Context of RFC 9053 section
+//! 5.2 with `AlgorithmID` A256GCM, empty `PartyUInfo` and `PartyVInfo`, and
+//! `SuppPubInfo` holding the key length in bits and the recipient's protected
+//! header.
+//!
+//! [`SharedKey`]: crate::SharedKey
+
+use aes_gcm::{
+  aead::{Aead, KeyInit, Payload},
+  Aes256Gcm,
+};
+pub use coset::{self, CoseEncrypt, CoseKey};
+use coset::{
+  cbor::value::Value, iana, Algorithm, CborSerializable, CoseEncryptBuilder, CoseKdfContextBuilder,
+  CoseRecipient, CoseRecipientBuilder, Header, HeaderBuilder, KeyType, Label, ProtectedHeader,
+  SuppPubInfo, TaggedCborSerializable,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::{mem, ops::Deref};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+  Error, ExpandedSecretKey, ParameterSet, PublicKey, SecretKey, ShareableSecret, SharedKey,
+  PARAMETER_SET,
+};
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const PUB: Label = Label::Int(iana::AkpKeyParameter::Pub as i64);
+const PRIV: Label = Label::Int(iana::AkpKeyParameter::Priv as i64);
+
+impl ParameterSet {
+  /// COSE `alg` of the parameter set, from the private use range (below
+  /// -65536).
+  pub const fn cose_alg(self) -> i64 {
+    match self {
+      Self::Mceliece348864 => -65537,
+      Self::Mceliece348864f => -65538,
+      Self::Mceliece460896 => -65539,
+      Self::Mceliece460896f => -65540,
+      Self::Mceliece6688128 => -65541,
+      Self::Mceliece6688128f => -65542,
+      Self::Mceliece6960119 => -65543,
+      Self::Mceliece6960119f => -65544,
+      Self::Mceliece8192128 => -65545,
+      Self::Mceliece8192128f => -65546,
+    }
+  }
+  pub fn from_cose_alg(alg: i64) -> Option<Self> {
+    Self::ALL.into_iter().find(|set| set.cose_alg() == alg)
+  }
+}
+
+fn algorithm() -> Algorithm {
+  Algorithm::PrivateUse(PARAMETER_SET.cose_alg())
+}
+
+pub fn public_key_to_cose_key(pk: &PublicKey) -> CoseKey {
+  CoseKey {
+    kty: KeyType::Assigned(iana::KeyType::AKP),
+    alg: Some(algorithm()),
+    params: vec![(PUB, Value::Bytes(pk.as_bytes().to_vec()))],
+    ..Default::default()
+  }
+}
+
+/// A [`CoseKey`] holding a secret key, whose byte string parameters are
+/// zeroized when it is dropped.
+pub struct SecretCoseKey(CoseKey);
+
+impl Deref for SecretCoseKey {
+  type Target = CoseKey;
+  fn deref(&self) -> &CoseKey {
+    &self.0
+  }
+}
+
+impl Drop for SecretCoseKey {
+  fn drop(&mut self) {
+    for (_, value) in &mut self.0.params {
+      if let Value::Bytes(bytes) = value {
+        bytes.zeroize();
+      }
+    }
+  }
+}
+
+/// Also carries the public key, which is recomputed from `sk`.
+pub fn secret_key_to_cose_key(sk: &SecretKey) -> Result<SecretCoseKey, Error> {
+  let mut key = SecretCoseKey(public_key_to_cose_key(&sk.public_key()?));
+  key.0.params.push((PRIV, Value::Bytes(sk.as_bytes().to_vec())));
+  Ok(key)
+}
+
+fn param<'a>(key: &'a CoseKey, label: &Label) -> Result<&'a [u8], Error> {
+  key
+    .params
+    .iter()
+    .find_map(|(l, value)| match value {
+      Value::Bytes(bytes) if l == label => Some(&bytes[..]),
+      _ => None,
+    })
+    .ok_or(Error::InvalidEncoding)
+}
+
+fn check_algorithm(key: &CoseKey) -> Result<(), Error> {
+  if key.kty != KeyType::Assigned(iana::KeyType::AKP) {
+    return Err(Error::InvalidEncoding);
+  }
+  if key.alg != Some(algorithm()) {
+    return Err(Error::AlgorithmMismatch);
+  }
+  Ok(())
+}
+
+/// Decodes `pub`. Fails with [`Error::AlgorithmMismatch`] when `alg` is not
+/// that of [`PARAMETER_SET`].
+pub fn public_key_from_cose_key(key: &CoseKey) -> Result<PublicKey, Error> {
+  check_algorithm(key)?;
+  PublicKey::try_from(param(key, &PUB)?)
+}
+
+/// Decodes `priv`, which has to be the secret key of `pub`.
+pub fn secret_key_from_cose_key(key: &CoseKey) -> Result<SecretKey, Error> {
+  check_algorithm(key)?;
+  let sk = SecretKey::try_from(param(key, &PRIV)?)?;
+  if sk.public_key()? != public_key_from_cose_key(key)? {
+    return Err(Error::KeyPairMismatch);
+  }
+  Ok(sk)
+}
+
+/// CBOR of the COSE_Key of `pk`.
+pub fn encode_public_key(pk: &PublicKey) -> Vec<u8> {
+  public_key_to_cose_key(pk).to_vec().unwrap()
+}
+
+/// CBOR of the COSE_Key of `sk`, including its public key.
+pub fn encode_secret_key(sk: &SecretKey) -> Result<Zeroizing<Vec<u8>>, Error> {
+  let mut key = secret_key_to_cose_key(sk)?;
+  Ok(Zeroizing::new(mem::take(&mut key.0).to_vec().unwrap()))
+}
+
+pub fn decode_public_key(cose_key: &[u8]) -> Result<PublicKey, Error> {
+  public_key_from_cose_key(&CoseKey::from_slice(cose_key).map_err(|_| Error::InvalidEncoding)?)
+}
+
+pub fn decode_secret_key(cose_key: &[u8]) -> Result<SecretKey, Error> {
+  let key = SecretCoseKey(CoseKey::from_slice(cose_key).map_err(|_| Error::InvalidEncoding)?);
+  secret_key_from_cose_key(&key)
+}
+
+/* RFC 9053 section 5.1, HKDF-SHA256 with the context of section 5.2 */
+fn content_key(key: &SharedKey, recipient: &ProtectedHeader) -> Zeroizing<[u8; KEY_LEN]> {
+  let context = CoseKdfContextBuilder::new()
+    .algorithm(iana::Algorithm::A256GCM)
+    .supp_pub_info(SuppPubInfo {
+      key_data_length: (KEY_LEN * 8) as u64,
+      protected: recipient.clone(),
+      other: None,
+    })
+    .build()
+    .to_vec()
+    .unwrap();
+  let mut content_key = Zeroizing::new([0u8; KEY_LEN]);
+  Hkdf::<Sha256>::new(None, key.as_bytes())
+    .expand(&context, content_key.as_mut())
+    .unwrap();
+  content_key
+}
+
+/// Encrypts `plaintext` to `pk` into a tagged COSE_Encrypt, authenticating
+/// `external_aad` along with it. `kid` goes into the recipient's unprotected
+/// header if given.
+#[cfg(feature = "openssl")]
+pub fn encrypt(
+  pk: &PublicKey,
+  plaintext: &[u8],
+  external_aad: &[u8],
+  kid: Option<&[u8]>,
+) -> Vec<u8> {
+  encrypt_with_entropy_provider(pk, plaintext, external_aad, kid, crate::crypto_random)
+}
+
+pub fn encrypt_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  plaintext: &[u8],
+  external_aad: &[u8],
+  kid: Option<&[u8]>,
+  mut entropy_provider: F,
+) -> Vec<u8> {
+  let (enc, key) = pk.encapsulate_with_entropy_provider(&mut entropy_provider);
+  let mut iv = [0u8; NONCE_LEN];
+  entropy_provider(&mut iv);
+
+  let recipient = CoseRecipientBuilder::new()
+    .protected(Header {
+      alg: Some(algorithm()),
+      ..Default::default()
+    })
+    .unprotected(Header {
+      key_id: kid.map(<[u8]>::to_vec).unwrap_or_default(),
+      ..Default::default()
+    })
+    .ciphertext(enc.as_bytes().to_vec())
+    .build();
+  let cipher = Aes256Gcm::new((&*content_key(&key, &recipient.protected)).into());
+  CoseEncryptBuilder::new()
+    .protected(
+      HeaderBuilder::new()
+        .algorithm(iana::Algorithm::A256GCM)
+        .build(),
+    )
+    .unprotected(HeaderBuilder::new().iv(iv.to_vec()).build())
+    .add_recipient(recipient)
+    .create_ciphertext(plaintext, external_aad, |msg, aad| {
+      cipher.encrypt((&iv).into(), Payload { msg, aad }).unwrap()
+    })
+    .build()
+    .to_tagged_vec()
+    .unwrap()
+}
+
+/// Decrypts a COSE_Encrypt, tagged or not, with the first recipient of the
+/// parameter set that `sk` can decrypt the content with.
+///
+/// Fails with [`Error::InvalidHeader`] when the message is malformed or has
+/// critical headers, with [`Error::AlgorithmMismatch`] when the content is not
+/// AES-256-GCM or no recipient is of the parameter set, and with
+/// [`Error::DecryptionFailed`] when no recipient is for `sk`.
+pub fn decrypt(sk: &SecretKey, message: &[u8], external_aad: &[u8]) -> Result<Vec<u8>, Error> {
+  decrypt_expanded(&sk.expand(), message, external_aad)
+}
+
+pub fn decrypt_expanded(
+  sk: &ExpandedSecretKey,
+  message: &[u8],
+  external_aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+  let message = CoseEncrypt::from_tagged_slice(message)
+    .or_else(|_| CoseEncrypt::from_slice(message))
+    .map_err(|_| Error::InvalidHeader)?;
+  if !message.protected.header.crit.is_empty() {
+    return Err(Error::InvalidHeader);
+  }
+  if message.protected.header.alg != Some(Algorithm::Assigned(iana::Algorithm::A256GCM)) {
+    return Err(Error::AlgorithmMismatch);
+  }
+  let iv: [u8; NONCE_LEN] = message
+    .unprotected
+    .iv
+    .as_slice()
+    .try_into()
+    .map_err(|_| Error::InvalidHeader)?;
+
+  let recipients: Vec<&CoseRecipient> = message
+    .recipients
+    .iter()
+    .filter(|recipient| recipient.protected.header.alg == Some(algorithm()))
+    .collect();
+  if recipients.is_empty() {
+    return Err(Error::AlgorithmMismatch);
+  }
+  for recipient in recipients {
+    if !recipient.protected.header.crit.is_empty() {
+      return Err(Error::InvalidHeader);
+    }
+    let enc = recipient
+      .ciphertext
+      .as_deref()
+      .ok_or(Error::InvalidHeader)?;
+    let enc = ShareableSecret::try_from(enc).map_err(|_| Error::InvalidHeader)?;
+    let Ok(key) = enc.try_decapsulate_expanded(sk) else {
+      continue;
+    };
+    let cipher = Aes256Gcm::new((&*content_key(&key, &recipient.protected)).into());
+    let plaintext = message.decrypt_ciphertext(
+      external_aad,
+      || Error::InvalidHeader,
+      |msg, aad| {
+        cipher
+          .decrypt((&iv).into(), Payload { msg, aad })
+          .map_err(|_| Error::DecryptionFailed)
+      },
+    );
+    match plaintext {
+      Err(Error::DecryptionFailed) => continue,
+      plaintext => return plaintext,
+    }
+  }
+  Err(Error::DecryptionFailed)
+}
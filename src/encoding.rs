@@ -0,0 +1,280 @@
+//! Serde representations of keys and ciphertexts.
+//!
+//! [`PublicKey`], [`SecretKey`], [`ShareableSecret`], [`SharedKey`] and
+//! [`PlainSecret`] serialize as byte strings for binary formats, and as
+//! standard padded base64 strings for human-readable ones (see
+//! [`serde::Serializer::is_human_readable`]). The [`base64`], [`hex`] and
+//! [`bytes`] modules pick the representation of a field with
+//! `#[serde(with = "...")]`:
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct Message {
+//!   #[serde(with = "rmce::encoding::hex")]
+//!   enc: ShareableSecret,
+//!   #[serde(with = "rmce::encoding::bytes")]
+//!   key: SharedKey,
+//! }
+//! ```
+//!
+//! Deserialization accepts strings in the representation's encoding, byte
+//! strings, and sequences of bytes such as the JSON arrays of integers written
+//! by earlier versions.
+
+use std::{fmt, marker::PhantomData};
+
+use ::base64::{
+  alphabet,
+  engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+  Engine,
+};
+use serde::{
+  de::{self, SeqAccess, Visitor},
+  Deserializer, Serializer,
+};
+use zeroize::Zeroizing;
+
+use crate::{Error, PlainSecret, PublicKey, SecretKey, ShareableSecret, SharedKey};
+
+/// Types that serialize as a string of bytes. Types that are not stored as one
+/// contiguous buffer, such as the hybrid keys, return an owned copy.
+pub trait ByteRepr: Sized {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_;
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+impl ByteRepr for PublicKey {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+    self.as_bytes()
+  }
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    Self::try_from(bytes)
+  }
+}
+
+impl ByteRepr for SecretKey {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+    self.as_bytes()
+  }
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    Self::try_from(bytes)
+  }
+}
+
+impl ByteRepr for ShareableSecret {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+    self.as_bytes()
+  }
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    Self::try_from(bytes)
+  }
+}
+
+impl ByteRepr for SharedKey {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+    self.as_bytes()
+  }
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    let key: [u8; Self::SIZE] = bytes.try_into().map_err(|_| Error::InvalidLength {
+      got: bytes.len(),
+      expected: Self::SIZE,
+    })?;
+    Ok(Self::from(key))
+  }
+}
+
+impl ByteRepr for PlainSecret {
+  fn repr_bytes(&self) -> impl AsRef<[u8]> + '_ {
+    self.as_bytes()
+  }
+  fn from_repr_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    Ok(Self::from(bytes.to_vec()))
+  }
+}
+
+trait Text {
+  const NAME: &'static str;
+  /* whether human-readable formats get a string */
+  const STRING: bool = true;
+  fn encode(bytes: &[u8]) -> String;
+  fn decode(text: &str) -> Option<Vec<u8>>;
+}
+
+/* padding is optional when decoding */
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+  &alphabet::STANDARD,
+  GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+struct Base64;
+
+impl Text for Base64 {
+  const NAME: &'static str = "a base64 string";
+  fn encode(bytes: &[u8]) -> String {
+    BASE64.encode(bytes)
+  }
+  fn decode(text: &str) -> Option<Vec<u8>> {
+    BASE64.decode(text).ok()
+  }
+}
+
+struct Hex;
+
+impl Text for Hex {
+  const NAME: &'static str = "a hex string";
+  fn encode(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+      text.push(DIGITS[(b >> 4) as usize] as char);
+      text.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    text
+  }
+  fn decode(text: &str) -> Option<Vec<u8>> {
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(2) {
+      return None;
+    }
+    text
+      .chunks(2)
+      .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+      .collect()
+  }
+}
+
+/* strings are not accepted */
+struct Raw;
+
+impl Text for Raw {
+  const NAME: &'static str = "a byte string";
+  const STRING: bool = false;
+  fn encode(_: &[u8]) -> String {
+    unreachable!()
+  }
+  fn decode(_: &str) -> Option<Vec<u8>> {
+    None
+  }
+}
+
+fn serialize_as<R: Text, T: ByteRepr, S: Serializer>(
+  value: &T,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  let bytes = value.repr_bytes();
+  if R::STRING && serializer.is_human_readable() {
+    let encoded = Zeroizing::new(R::encode(bytes.as_ref()));
+    serializer.serialize_str(&encoded)
+  } else {
+    serializer.serialize_bytes(bytes.as_ref())
+  }
+}
+
+fn deserialize_as<'de, R: Text, T: ByteRepr, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<T, D::Error> {
+  let visitor = ReprVisitor::<R, T>(PhantomData);
+  if deserializer.is_human_readable() {
+    deserializer.deserialize_any(visitor)
+  } else {
+    deserializer.deserialize_byte_buf(visitor)
+  }
+}
+
+struct ReprVisitor<R, T>(PhantomData<(R, T)>);
+
+impl<R: Text, T: ByteRepr> ReprVisitor<R, T> {
+  fn value<E: de::Error>(bytes: &[u8]) -> Result<T, E> {
+    T::from_repr_bytes(bytes).map_err(|err| match err {
+      Error::InvalidLength { got, expected } => {
+        E::invalid_length(got, &format!("{expected} bytes").as_str())
+      }
+      err => E::custom(format!("{err:?}")),
+    })
+  }
+}
+
+impl<'de, R: Text, T: ByteRepr> Visitor<'de> for ReprVisitor<R, T> {
+  type Value = T;
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    write!(formatter, "{} or a sequence of bytes", R::NAME)
+  }
+  fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+    let bytes = Zeroizing::new(
+      R::decode(v).ok_or_else(|| E::invalid_type(de::Unexpected::Other("string"), &self))?,
+    );
+    Self::value(&bytes)
+  }
+  fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<T, E> {
+    Self::value(v)
+  }
+  fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<T, E> {
+    Self::value(&Zeroizing::new(v))
+  }
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+    /* the size hint is not trusted for more than a public key */
+    let capacity = seq.size_hint().unwrap_or(0).min(PublicKey::SIZE);
+    let mut bytes = Zeroizing::new(Vec::with_capacity(capacity));
+    while let Some(b) = seq.next_element::<u8>()? {
+      bytes.push(b);
+    }
+    Self::value(&bytes)
+  }
+}
+
+/// Base64 strings for human-readable formats and byte strings otherwise, the
+/// default representation.
+pub mod base64 {
+  use super::*;
+
+  pub fn serialize<T: ByteRepr, S: Serializer>(
+    value: &T,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serialize_as::<Base64, _, _>(value, serializer)
+  }
+
+  pub fn deserialize<'de, T: ByteRepr, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<T, D::Error> {
+    deserialize_as::<Base64, _, _>(deserializer)
+  }
+}
+
+/// Lowercase hex strings for human-readable formats and byte strings
+/// otherwise. Either case is accepted.
+pub mod hex {
+  use super::*;
+
+  pub fn serialize<T: ByteRepr, S: Serializer>(
+    value: &T,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serialize_as::<Hex, _, _>(value, serializer)
+  }
+
+  pub fn deserialize<'de, T: ByteRepr, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<T, D::Error> {
+    deserialize_as::<Hex, _, _>(deserializer)
+  }
+}
+
+/// Byte strings for all formats, which is an array of integers in JSON.
+pub mod bytes {
+  use super::*;
+
+  pub fn serialize<T: ByteRepr, S: Serializer>(
+    value: &T,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serialize_as::<Raw, _, _>(value, serializer)
+  }
+
+  pub fn deserialize<'de, T: ByteRepr, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<T, D::Error> {
+    deserialize_as::<Raw, _, _>(deserializer)
+  }
+}
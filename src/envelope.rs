@@ -0,0 +1,159 @@
+//! Encryption of one payload to several public keys.
+//!
+//! # Format (version 1)
+//!
+//! ```text
+//! envelope = "RMCM" || version (0x01) || count (u16, big-endian) || stanza * count
+//!            || payload
+//! stanza   = ShareableSecret || wrapped data key (32 + 16 bytes)
+//! ```
+//!
+//! A random 32-byte data key is wrapped for every recipient with
+//! ChaCha20-Poly1305 under SHAKE256 of `"rmce-envelope-wrap-v1"`, the
+//! recipient's [`crate::SharedKey`] and the [`ShareableSecret`]. The payload
+//! is sealed with ChaCha20-Poly1305 under KMAC256 of the data key, and the
+//! whole header (everything before the payload) is authenticated with it
+//! together with the caller's associated data. Adding, removing, reordering
+//! or altering a stanza therefore makes the payload fail to decrypt, even for
+//! recipients whose own stanza is intact.
+
+use chacha20poly1305::{
+  aead::{Aead, KeyInit, Payload},
+  ChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  impls::libkeccak::{kmac256, Sponge, SHAKE256_RATE, SHAKE_SUFFIX},
+  Error, ExpandedSecretKey, PublicKey, SecretKey, ShareableSecret, SharedKey,
+};
+
+const MAGIC: &[u8; 4] = b"RMCM";
+const VERSION: u8 = 1;
+const WRAP_DOMAIN: &[u8] = b"rmce-envelope-wrap-v1";
+const PAYLOAD_LABEL: &[u8] = b"rmce-envelope-payload-v1";
+const PREFIX_LEN: usize = MAGIC.len() + 1 + 2;
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+const NONCE: [u8; 12] = [0u8; 12];
+
+/// Length of one recipient stanza.
+pub const STANZA_LEN: usize = ShareableSecret::SIZE + KEY_LEN + TAG_LEN;
+/// Largest number of recipients of one envelope.
+pub const MAX_RECIPIENTS: usize = u16::MAX as usize;
+
+/// Encrypts `plaintext` to every key in `recipients`, authenticating `aad`
+/// along with it.
+#[cfg(feature = "openssl")]
+pub fn seal(recipients: &[PublicKey], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+  seal_with_entropy_provider(recipients, plaintext, aad, crate::crypto_random)
+}
+
+pub fn seal_with_entropy_provider<F: FnMut(&mut [u8])>(
+  recipients: &[PublicKey],
+  plaintext: &[u8],
+  aad: &[u8],
+  mut entropy_provider: F,
+) -> Result<Vec<u8>, Error> {
+  if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+    return Err(Error::InvalidRecipientCount {
+      got: recipients.len(),
+      max: MAX_RECIPIENTS,
+    });
+  }
+
+  let mut data_key = Zeroizing::new([0u8; KEY_LEN]);
+  entropy_provider(data_key.as_mut());
+
+  let header_len = PREFIX_LEN + recipients.len() * STANZA_LEN;
+  let mut envelope = Vec::with_capacity(header_len + plaintext.len() + TAG_LEN);
+  envelope.extend_from_slice(MAGIC);
+  envelope.push(VERSION);
+  envelope.extend_from_slice(&(recipients.len() as u16).to_be_bytes());
+  for pk in recipients {
+    let (enc, key) = pk.encapsulate_with_entropy_provider(&mut entropy_provider);
+    let wrapped = wrap_cipher(&key, &enc)
+      .encrypt((&NONCE).into(), data_key.as_ref())
+      .unwrap();
+    envelope.extend_from_slice(enc.as_bytes());
+    envelope.extend_from_slice(&wrapped);
+  }
+
+  let ciphertext = payload_cipher(&data_key[..])
+    .encrypt(
+      (&NONCE).into(),
+      Payload {
+        msg: plaintext,
+        aad: &[&envelope[..], aad].concat(),
+      },
+    )
+    .unwrap();
+  envelope.extend_from_slice(&ciphertext);
+  Ok(envelope)
+}
+
+/// Decrypts an envelope with whichever stanza `sk` can unwrap.
+pub fn open(sk: &SecretKey, envelope: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+  open_expanded(&sk.expand(), envelope, aad)
+}
+
+pub fn open_expanded(
+  sk: &ExpandedSecretKey,
+  envelope: &[u8],
+  aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+  if envelope.len() < PREFIX_LEN {
+    return Err(Error::InvalidLength {
+      got: envelope.len(),
+      expected: PREFIX_LEN,
+    });
+  }
+  if &envelope[..MAGIC.len()] != MAGIC || envelope[MAGIC.len()] != VERSION {
+    return Err(Error::InvalidHeader);
+  }
+  let count = u16::from_be_bytes([envelope[PREFIX_LEN - 2], envelope[PREFIX_LEN - 1]]) as usize;
+  let header_len = PREFIX_LEN + count * STANZA_LEN;
+  if count == 0 || envelope.len() < header_len + TAG_LEN {
+    return Err(Error::InvalidHeader);
+  }
+  let (header, ciphertext) = envelope.split_at(header_len);
+
+  let data_key = header[PREFIX_LEN..]
+    .chunks_exact(STANZA_LEN)
+    .find_map(|stanza| {
+      let (enc, wrapped) = stanza.split_at(ShareableSecret::SIZE);
+      let enc = ShareableSecret::try_from(enc).unwrap();
+      let key = enc.try_decapsulate_expanded(sk).ok()?;
+      let data_key = wrap_cipher(&key, &enc)
+        .decrypt((&NONCE).into(), wrapped)
+        .ok()?;
+      Some(Zeroizing::new(data_key))
+    })
+    .ok_or(Error::DecryptionFailed)?;
+
+  payload_cipher(&data_key[..])
+    .decrypt(
+      (&NONCE).into(),
+      Payload {
+        msg: ciphertext,
+        aad: &[header, aad].concat(),
+      },
+    )
+    .map_err(|_| Error::DecryptionFailed)
+}
+
+fn wrap_cipher(key: &SharedKey, enc: &ShareableSecret) -> ChaCha20Poly1305 {
+  let mut wrap_key = Zeroizing::new([0u8; KEY_LEN]);
+  let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+  hash.absorb(WRAP_DOMAIN);
+  hash.absorb(key.as_bytes());
+  hash.absorb(enc.as_bytes());
+  hash.squeeze(wrap_key.as_mut());
+  ChaCha20Poly1305::new_from_slice(wrap_key.as_ref()).unwrap()
+}
+
+fn payload_cipher(data_key: &[u8]) -> ChaCha20Poly1305 {
+  let mut payload_key = Zeroizing::new([0u8; KEY_LEN]);
+  kmac256(payload_key.as_mut(), data_key, &[], PAYLOAD_LABEL);
+  ChaCha20Poly1305::new_from_slice(payload_key.as_ref()).unwrap()
+}
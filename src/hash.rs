@@ -0,0 +1,260 @@
+//! SHA-3 family (FIPS 202) and cSHAKE/KMAC (NIST SP 800-185), built on the same
+//! Keccak implementation the KEM uses internally.
+//!
+//! Every type can be fed incrementally with `update`. The XOFs are finished with
+//! `finalize_xof`, which returns an [`XofReader`] to squeeze any amount of output.
+//! The [`digest`] traits are implemented as well, so the types plug into code
+//! that is generic over RustCrypto hashes.
+
+pub use digest;
+
+use digest::{
+  consts::{U32, U64},
+  ExtendableOutput, FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset,
+  Update,
+};
+
+use crate::impls::libkeccak::{
+  right_encode, Sponge, SHA3_256_RATE, SHA3_512_RATE, SHA3_SUFFIX, SHAKE128_RATE, SHAKE256_RATE,
+  SHAKE_SUFFIX,
+};
+
+/// Output stream of an extendable-output function.
+#[derive(Clone)]
+pub struct XofReader(Sponge);
+
+impl XofReader {
+  pub fn read(&mut self, out: &mut [u8]) {
+    self.0.squeeze(out);
+  }
+}
+
+impl digest::XofReader for XofReader {
+  fn read(&mut self, buffer: &mut [u8]) {
+    self.0.squeeze(buffer);
+  }
+}
+
+macro_rules! shake {
+  ($(#[$doc:meta])* $name:ident, $rate:expr) => {
+    $(#[$doc])*
+    #[derive(Clone)]
+    pub struct $name(Sponge);
+
+    impl $name {
+      pub fn new() -> Self {
+        Self(Sponge::new($rate, SHAKE_SUFFIX))
+      }
+      pub fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+      pub fn finalize_xof(self) -> XofReader {
+        XofReader(self.0)
+      }
+    }
+
+    impl Default for $name {
+      fn default() -> Self {
+        Self::new()
+      }
+    }
+
+    impl HashMarker for $name {}
+
+    impl Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+    }
+
+    impl ExtendableOutput for $name {
+      type Reader = XofReader;
+      fn finalize_xof(self) -> XofReader {
+        XofReader(self.0)
+      }
+    }
+
+    impl Reset for $name {
+      fn reset(&mut self) {
+        *self = Self::new();
+      }
+    }
+  };
+}
+
+shake!(
+  /// SHAKE128 extendable-output function.
+  Shake128,
+  SHAKE128_RATE
+);
+shake!(
+  /// SHAKE256 extendable-output function.
+  Shake256,
+  SHAKE256_RATE
+);
+
+macro_rules! sha3 {
+  ($(#[$doc:meta])* $name:ident, $rate:expr, $size:ty, $len:expr) => {
+    $(#[$doc])*
+    #[derive(Clone)]
+    pub struct $name(Sponge);
+
+    impl $name {
+      pub fn new() -> Self {
+        Self(Sponge::new($rate, SHA3_SUFFIX))
+      }
+      pub fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+      pub fn finalize(mut self) -> [u8; $len] {
+        let mut out = [0u8; $len];
+        self.0.squeeze(&mut out);
+        out
+      }
+    }
+
+    impl Default for $name {
+      fn default() -> Self {
+        Self::new()
+      }
+    }
+
+    impl HashMarker for $name {}
+
+    impl Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+    }
+
+    impl OutputSizeUser for $name {
+      type OutputSize = $size;
+    }
+
+    impl FixedOutput for $name {
+      fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.0.squeeze(out);
+      }
+    }
+
+    impl Reset for $name {
+      fn reset(&mut self) {
+        *self = Self::new();
+      }
+    }
+
+    impl FixedOutputReset for $name {
+      fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        std::mem::take(self).finalize_into(out);
+      }
+    }
+  };
+}
+
+sha3!(
+  /// SHA3-256 hash function.
+  Sha3_256,
+  SHA3_256_RATE,
+  U32,
+  32
+);
+sha3!(
+  /// SHA3-512 hash function.
+  Sha3_512,
+  SHA3_512_RATE,
+  U64,
+  64
+);
+
+macro_rules! cshake {
+  ($(#[$doc:meta])* $name:ident, $rate:expr) => {
+    $(#[$doc])*
+    #[derive(Clone)]
+    pub struct $name(Sponge);
+
+    impl $name {
+      /// `name` is the function-name string N, `custom` the customization string S.
+      /// With both empty this is plain SHAKE.
+      pub fn new(name: &[u8], custom: &[u8]) -> Self {
+        Self(Sponge::cshake($rate, name, custom))
+      }
+      pub fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+      pub fn finalize_xof(self) -> XofReader {
+        XofReader(self.0)
+      }
+    }
+
+    impl HashMarker for $name {}
+
+    impl Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+    }
+
+    impl ExtendableOutput for $name {
+      type Reader = XofReader;
+      fn finalize_xof(self) -> XofReader {
+        XofReader(self.0)
+      }
+    }
+  };
+}
+
+cshake!(
+  /// cSHAKE128 customizable extendable-output function.
+  CShake128,
+  SHAKE128_RATE
+);
+cshake!(
+  /// cSHAKE256 customizable extendable-output function.
+  CShake256,
+  SHAKE256_RATE
+);
+
+macro_rules! kmac {
+  ($(#[$doc:meta])* $name:ident, $rate:expr) => {
+    $(#[$doc])*
+    #[derive(Clone)]
+    pub struct $name(Sponge);
+
+    impl $name {
+      /// `custom` is the customization string S.
+      pub fn new(key: &[u8], custom: &[u8]) -> Self {
+        Self(Sponge::kmac($rate, key, custom))
+      }
+      pub fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+      /// Fills `out` with a tag; its length is bound into the result.
+      pub fn finalize_into(mut self, out: &mut [u8]) {
+        self.0.absorb(&right_encode(out.len() as u64 * 8));
+        self.0.squeeze(out);
+      }
+      /// Finishes as the KMACXOF variant, with an arbitrary-length output.
+      pub fn finalize_xof(mut self) -> XofReader {
+        self.0.absorb(&right_encode(0));
+        XofReader(self.0)
+      }
+    }
+
+    impl Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        self.0.absorb(data);
+      }
+    }
+  };
+}
+
+kmac!(
+  /// KMAC128 message authentication code.
+  Kmac128,
+  SHAKE128_RATE
+);
+kmac!(
+  /// KMAC256 message authentication code.
+  Kmac256,
+  SHAKE256_RATE
+);
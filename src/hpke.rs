@@ -0,0 +1,429 @@
+//! Hybrid Public Key Encryption (RFC 9180) with Classic McEliece as the KEM,
+//! HKDF-SHA256 as the KDF and AES-GCM or ChaCha20-Poly1305 as the AEAD.
+//!
+//! The KEM shared secret is the [`SharedKey`] returned by
+//! [`PublicKey::encapsulate`], and `enc` is the [`ShareableSecret`]. Only the
+//! base and PSK modes are supported: the auth modes need a KEM that can
+//! authenticate the sender (`AuthEncap`), which McEliece cannot do. Sender
+//! authentication can be had from a pre-shared key instead.
+
+use aes_gcm::{
+  aead::{Aead as _, KeyInit, Payload},
+  Aes128Gcm, Aes256Gcm,
+};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{Error, PlainSecret, PublicKey, SecretKey, ShareableSecret, SharedKey};
+
+/// KEM identifier used in the HPKE suite ID. McEliece has no IANA-assigned
+/// value, so this one is private to this crate.
+pub const KEM_ID: u16 = 0xff01;
+/// HKDF-SHA256.
+pub const KDF_ID: u16 = 0x0001;
+
+const NH: usize = 32;
+const NN: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aead {
+  Aes128Gcm,
+  Aes256Gcm,
+  ChaCha20Poly1305,
+}
+
+impl Aead {
+  pub fn id(self) -> u16 {
+    match self {
+      Aead::Aes128Gcm => 0x0001,
+      Aead::Aes256Gcm => 0x0002,
+      Aead::ChaCha20Poly1305 => 0x0003,
+    }
+  }
+  pub fn key_len(self) -> usize {
+    match self {
+      Aead::Aes128Gcm => 16,
+      Aead::Aes256Gcm | Aead::ChaCha20Poly1305 => 32,
+    }
+  }
+  fn seal(self, key: &[u8], nonce: &[u8; NN], aad: &[u8], msg: &[u8]) -> Vec<u8> {
+    let payload = Payload { msg, aad };
+    let nonce = nonce.into();
+    match self {
+      Aead::Aes128Gcm => Aes128Gcm::new_from_slice(key)
+        .unwrap()
+        .encrypt(nonce, payload),
+      Aead::Aes256Gcm => Aes256Gcm::new_from_slice(key)
+        .unwrap()
+        .encrypt(nonce, payload),
+      Aead::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)
+        .unwrap()
+        .encrypt(nonce, payload),
+    }
+    .unwrap()
+  }
+  fn open(self, key: &[u8], nonce: &[u8; NN], aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, Error> {
+    let payload = Payload { msg, aad };
+    let nonce = nonce.into();
+    match self {
+      Aead::Aes128Gcm => Aes128Gcm::new_from_slice(key)
+        .unwrap()
+        .decrypt(nonce, payload),
+      Aead::Aes256Gcm => Aes256Gcm::new_from_slice(key)
+        .unwrap()
+        .decrypt(nonce, payload),
+      Aead::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)
+        .unwrap()
+        .decrypt(nonce, payload),
+    }
+    .map_err(|_| Error::DecryptionFailed)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode<'a> {
+  Base,
+  /// Both the key and its identifier must be non-empty. RFC 9180 recommends a
+  /// key with at least 32 bytes of entropy.
+  Psk {
+    psk: &'a [u8],
+    psk_id: &'a [u8],
+  },
+}
+
+impl Mode<'_> {
+  fn id(&self) -> u8 {
+    match self {
+      Mode::Base => 0x00,
+      Mode::Psk { .. } => 0x01,
+    }
+  }
+  fn psk(&self) -> Result<(&[u8], &[u8]), Error> {
+    match *self {
+      Mode::Base => Ok((&[], &[])),
+      Mode::Psk { psk, psk_id } if !psk.is_empty() && !psk_id.is_empty() => Ok((psk, psk_id)),
+      Mode::Psk { .. } => Err(Error::InvalidPsk),
+    }
+  }
+}
+
+struct Context {
+  suite_id: [u8; 10],
+  aead: Aead,
+  key: Zeroizing<Vec<u8>>,
+  base_nonce: [u8; NN],
+  seq: u64,
+  exporter_secret: Zeroizing<[u8; NH]>,
+}
+
+impl Context {
+  fn nonce(&self) -> Result<[u8; NN], Error> {
+    if self.seq == u64::MAX {
+      return Err(Error::MessageLimitReached);
+    }
+    let mut nonce = self.base_nonce;
+    for (n, s) in nonce[NN - 8..].iter_mut().zip(self.seq.to_be_bytes()) {
+      *n ^= s;
+    }
+    Ok(nonce)
+  }
+  fn export(&self, exporter_context: &[u8], len: usize) -> Result<PlainSecret, Error> {
+    let mut out = PlainSecret(vec![0u8; len]);
+    labeled_expand(
+      &self.suite_id,
+      &self.exporter_secret,
+      b"sec",
+      exporter_context,
+      &mut out.0,
+    )?;
+    Ok(out)
+  }
+}
+
+/// Encryption context of the sender. Messages must be opened in the order they
+/// were sealed.
+pub struct SenderContext(Context);
+
+impl SenderContext {
+  pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let nonce = self.0.nonce()?;
+    let ciphertext = self.0.aead.seal(&self.0.key, &nonce, aad, plaintext);
+    self.0.seq += 1;
+    Ok(ciphertext)
+  }
+  /// Derives a secret from the context, see RFC 9180 section 5.3.
+  pub fn export(&self, exporter_context: &[u8], len: usize) -> Result<PlainSecret, Error> {
+    self.0.export(exporter_context, len)
+  }
+}
+
+/// Decryption context of the recipient.
+pub struct ReceiverContext(Context);
+
+impl ReceiverContext {
+  pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    let nonce = self.0.nonce()?;
+    let plaintext = self.0.aead.open(&self.0.key, &nonce, aad, ciphertext)?;
+    self.0.seq += 1;
+    Ok(plaintext)
+  }
+  /// Derives a secret from the context, see RFC 9180 section 5.3.
+  pub fn export(&self, exporter_context: &[u8], len: usize) -> Result<PlainSecret, Error> {
+    self.0.export(exporter_context, len)
+  }
+}
+
+#[cfg(feature = "openssl")]
+pub fn setup_sender(
+  pk: &PublicKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+) -> Result<(ShareableSecret, SenderContext), Error> {
+  setup_sender_with_entropy_provider(pk, aead, mode, info, crate::crypto_random)
+}
+
+pub fn setup_sender_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+  entropy_provider: F,
+) -> Result<(ShareableSecret, SenderContext), Error> {
+  mode.psk()?;
+  let (enc, shared_secret) = pk.encapsulate_with_entropy_provider(entropy_provider);
+  let ctx = key_schedule(KEM_ID, aead, mode, &shared_secret, info)?;
+  Ok((enc, SenderContext(ctx)))
+}
+
+pub fn setup_receiver(
+  enc: &ShareableSecret,
+  sk: &SecretKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+) -> Result<ReceiverContext, Error> {
+  mode.psk()?;
+  let shared_secret = enc.try_decapsulate(sk)?;
+  let ctx = key_schedule(KEM_ID, aead, mode, &shared_secret, info)?;
+  Ok(ReceiverContext(ctx))
+}
+
+/// Single-shot encryption of one message.
+#[cfg(feature = "openssl")]
+pub fn seal(
+  pk: &PublicKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+  aad: &[u8],
+  plaintext: &[u8],
+) -> Result<(ShareableSecret, Vec<u8>), Error> {
+  seal_with_entropy_provider(pk, aead, mode, info, aad, plaintext, crate::crypto_random)
+}
+
+pub fn seal_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+  aad: &[u8],
+  plaintext: &[u8],
+  entropy_provider: F,
+) -> Result<(ShareableSecret, Vec<u8>), Error> {
+  let (enc, mut ctx) = setup_sender_with_entropy_provider(pk, aead, mode, info, entropy_provider)?;
+  Ok((enc, ctx.seal(aad, plaintext)?))
+}
+
+/// Single-shot decryption of a message sealed with `seal`.
+pub fn open(
+  enc: &ShareableSecret,
+  sk: &SecretKey,
+  aead: Aead,
+  mode: Mode,
+  info: &[u8],
+  aad: &[u8],
+  ciphertext: &[u8],
+) -> Result<Vec<u8>, Error> {
+  setup_receiver(enc, sk, aead, mode, info)?.open(aad, ciphertext)
+}
+
+fn suite_id(kem_id: u16, aead: Aead) -> [u8; 10] {
+  let mut id = *b"HPKE\0\0\0\0\0\0";
+  id[4..6].copy_from_slice(&kem_id.to_be_bytes());
+  id[6..8].copy_from_slice(&KDF_ID.to_be_bytes());
+  id[8..].copy_from_slice(&aead.id().to_be_bytes());
+  id
+}
+
+fn key_schedule(
+  kem_id: u16,
+  aead: Aead,
+  mode: Mode,
+  shared_secret: &SharedKey,
+  info: &[u8],
+) -> Result<Context, Error> {
+  let suite_id = suite_id(kem_id, aead);
+  let (psk, psk_id) = mode.psk()?;
+
+  let psk_id_hash = labeled_extract(&suite_id, b"", b"psk_id_hash", psk_id);
+  let info_hash = labeled_extract(&suite_id, b"", b"info_hash", info);
+  let mut key_schedule_context = Vec::with_capacity(1 + 2 * NH);
+  key_schedule_context.push(mode.id());
+  key_schedule_context.extend_from_slice(psk_id_hash.as_ref());
+  key_schedule_context.extend_from_slice(info_hash.as_ref());
+
+  let secret = labeled_extract(&suite_id, shared_secret.as_bytes(), b"secret", psk);
+
+  let mut ctx = Context {
+    suite_id,
+    aead,
+    key: Zeroizing::new(vec![0u8; aead.key_len()]),
+    base_nonce: [0u8; NN],
+    seq: 0,
+    exporter_secret: Zeroizing::new([0u8; NH]),
+  };
+  labeled_expand(
+    &suite_id,
+    &secret,
+    b"key",
+    &key_schedule_context,
+    &mut ctx.key,
+  )?;
+  labeled_expand(
+    &suite_id,
+    &secret,
+    b"base_nonce",
+    &key_schedule_context,
+    &mut ctx.base_nonce,
+  )?;
+  labeled_expand(
+    &suite_id,
+    &secret,
+    b"exp",
+    &key_schedule_context,
+    ctx.exporter_secret.as_mut(),
+  )?;
+  Ok(ctx)
+}
+
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Zeroizing<[u8; NH]> {
+  let mut labeled_ikm = Zeroizing::new(Vec::with_capacity(
+    7 + suite_id.len() + label.len() + ikm.len(),
+  ));
+  labeled_ikm.extend_from_slice(b"HPKE-v1");
+  labeled_ikm.extend_from_slice(suite_id);
+  labeled_ikm.extend_from_slice(label);
+  labeled_ikm.extend_from_slice(ikm);
+  let (prk, _) = Hkdf::<Sha256>::extract(Some(salt), &labeled_ikm);
+  Zeroizing::new(prk.into())
+}
+
+fn labeled_expand(
+  suite_id: &[u8],
+  prk: &[u8; NH],
+  label: &[u8],
+  info: &[u8],
+  out: &mut [u8],
+) -> Result<(), Error> {
+  const MAX: usize = 255 * NH;
+  let len = u16::try_from(out.len()).map_err(|_| Error::OutputTooLong {
+    got: out.len(),
+    max: MAX,
+  })?;
+  let mut labeled_info = Vec::with_capacity(2 + 7 + suite_id.len() + label.len() + info.len());
+  labeled_info.extend_from_slice(&len.to_be_bytes());
+  labeled_info.extend_from_slice(b"HPKE-v1");
+  labeled_info.extend_from_slice(suite_id);
+  labeled_info.extend_from_slice(label);
+  labeled_info.extend_from_slice(info);
+  Hkdf::<Sha256>::from_prk(prk)
+    .unwrap()
+    .expand(&labeled_info, out)
+    .map_err(|_| Error::OutputTooLong {
+      got: out.len(),
+      max: MAX,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+      .collect()
+  }
+
+  /* RFC 9180 appendix A.1 and A.2 (DHKEM(X25519, HKDF-SHA256), KEM ID 0x0020),
+  checked from the KEM shared secret on */
+  #[test]
+  fn rfc9180_key_schedule() {
+    let info = hex("4f6465206f6e2061204772656369616e2055726e");
+    let psk = hex("0247fd33b913760fa1fa51e1892d9f307fbe65eb171e8132c2af18555a738b82");
+    let psk_id = hex("456e6e796e20447572696e206172616e204d6f726961");
+    let aad = hex("436f756e742d30");
+    let pt = hex("4265617574792069732074727574682c20747275746820626561757479");
+    let vectors = [
+      (
+        Aead::Aes128Gcm,
+        Mode::Base,
+        "fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc",
+        "4531685d41d65f03dc48f6b8302c05b0",
+        "56d890e5accaaf011cff4b7d",
+        "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a96d8770ac83d07bea87e13c512a",
+        "3853fe2b4035195a573ffc53856e77058e15d9ea064de3e59f4961d0095250ee",
+      ),
+      (
+        Aead::Aes128Gcm,
+        Mode::Psk {
+          psk: &psk,
+          psk_id: &psk_id,
+        },
+        "727699f009ffe3c076315019c69648366b69171439bd7dd0807743bde76986cd",
+        "15026dba546e3ae05836fc7de5a7bb26",
+        "9518635eba129d5ce0914555",
+        "e52c6fed7f758d0cf7145689f21bc1be6ec9ea097fef4e959440012f4feb73fb611b946199e681f4cfc34db8ea",
+        "dff17af354c8b41673567db6259fd6029967b4e1aad13023c2ae5df8f4f43bf6",
+      ),
+      (
+        Aead::ChaCha20Poly1305,
+        Mode::Base,
+        "0bbe78490412b4bbea4812666f7916932b828bba79942424abb65244930d69a7",
+        "ad2744de8e17f4ebba575b3f5f5a8fa1f69c2a07f6e7500bc60ca6e3e3ec1c91",
+        "5c4d98150661b848853b547f",
+        "1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28",
+        "4bbd6243b8bb54cec311fac9df81841b6fd61f56538a775e7c80a9f40160606e",
+      ),
+      (
+        Aead::ChaCha20Poly1305,
+        Mode::Psk {
+          psk: &psk,
+          psk_id: &psk_id,
+        },
+        "4be079c5e77779d0215b3f689595d59e3e9b0455d55662d1f3666ec606e50ea7",
+        "600d2fdb0313a7e5c86a9ce9221cd95bed069862421744cfb4ab9d7203a9c019",
+        "112e0465562045b7368653e7",
+        "4a177f9c0d6f15cfdf533fb65bf84aecdc6ab16b8b85b4cf65a370e07fc1d78d28fb073214525276f4a89608ff",
+        "813c1bfc516c99076ae0f466671f0ba5ff244a41699f7b2417e4c59d46d39f40",
+      ),
+    ];
+    for (aead, mode, shared_secret, key, base_nonce, ct, exported) in vectors {
+      let shared_secret = SharedKey(hex(shared_secret).try_into().unwrap());
+      let ctx = key_schedule(0x0020, aead, mode, &shared_secret, &info).unwrap();
+      assert_eq!(&ctx.key[..], &hex(key)[..]);
+      assert_eq!(&ctx.base_nonce[..], &hex(base_nonce)[..]);
+
+      let mut sender = SenderContext(ctx);
+      assert_eq!(sender.seal(&aad, &pt).unwrap(), hex(ct));
+      assert_eq!(
+        sender.export(b"", 32).unwrap().as_bytes(),
+        &hex(exported)[..]
+      );
+    }
+  }
+}
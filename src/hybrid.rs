@@ -0,0 +1,80 @@
+//! Hybrid KEM combining Classic McEliece with X25519.
+//!
+//! The shared key stays secret as long as either of the two components is
+//! unbroken. It is computed as in the X-Wing and KEM-combiner drafts:
+//!
+//! ```text
+//! SHA3-256(LABEL || ss_M || ss_X || ct_M || ct_X || SHA3-256(pk_M) || pk_X)
+//! ```
+//!
+//! where `M` is the McEliece part and `X` the X25519 part. Every field has a
+//! fixed length. The McEliece public key is too large to hash on every call,
+//! so its digest is kept in both [`PublicKey`] and [`SecretKey`].
+//!
+//! All byte encodings are the McEliece part followed by the X25519 part.
+
+use zeroize::Zeroizing;
+
+use crate::{
+  combiner::{array, hybrid_kem, Component},
+  Error,
+};
+
+pub use x25519_dalek;
+
+const X25519_LEN: usize = 32;
+
+struct X25519;
+
+impl Component for X25519 {
+  type PublicKey = x25519_dalek::PublicKey;
+  type SecretKey = x25519_dalek::StaticSecret;
+  const PUBLIC_KEY_LEN: usize = X25519_LEN;
+  const SECRET_KEY_LEN: usize = X25519_LEN;
+  const CIPHERTEXT_LEN: usize = X25519_LEN;
+  fn public_key_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+    pk.as_bytes().to_vec()
+  }
+  fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, Error> {
+    array::<u8, X25519_LEN>(bytes).map(Into::into)
+  }
+  fn secret_key_bytes(sk: &Self::SecretKey) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(sk.as_bytes().to_vec())
+  }
+  fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, Error> {
+    let mut bytes = Zeroizing::new(array::<u8, X25519_LEN>(bytes)?);
+    Ok(std::mem::take(&mut *bytes).into())
+  }
+  fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+    sk.into()
+  }
+  fn generate<F: FnMut(&mut [u8])>(mut entropy_provider: F) -> (Self::PublicKey, Self::SecretKey) {
+    let mut seed = Zeroizing::new([0u8; X25519_LEN]);
+    entropy_provider(seed.as_mut());
+    let sk = x25519_dalek::StaticSecret::from(*seed);
+    ((&sk).into(), sk)
+  }
+  fn encapsulate<F: FnMut(&mut [u8])>(
+    pk: &Self::PublicKey,
+    ct: &mut [u8],
+    mut entropy_provider: F,
+  ) -> Zeroizing<Vec<u8>> {
+    let mut ephemeral = Zeroizing::new([0u8; X25519_LEN]);
+    entropy_provider(ephemeral.as_mut());
+    let ephemeral = x25519_dalek::StaticSecret::from(*ephemeral);
+    ct.copy_from_slice(x25519_dalek::PublicKey::from(&ephemeral).as_bytes());
+    Zeroizing::new(ephemeral.diffie_hellman(pk).as_bytes().to_vec())
+  }
+  fn decapsulate(sk: &Self::SecretKey, ct: &[u8]) -> Zeroizing<Vec<u8>> {
+    let ct = x25519_dalek::PublicKey::from(<[u8; X25519_LEN]>::try_from(ct).unwrap());
+    Zeroizing::new(sk.diffie_hellman(&ct).as_bytes().to_vec())
+  }
+}
+
+hybrid_kem!(
+  component: X25519,
+  name: x25519,
+  public_key: x25519_dalek::PublicKey,
+  secret_key: x25519_dalek::StaticSecret,
+  label: b"rmce-hybrid-x25519-v1",
+);
@@ -0,0 +1,137 @@
+//! Hybrid KEMs combining Classic McEliece with ML-KEM (FIPS 203), so that the
+//! shared key rests on two unrelated post-quantum assumptions.
+//!
+//! The shared key is computed as
+//!
+//! ```text
+//! SHA3-256(LABEL || ss_M || ss_K || ct_M || ct_K || SHA3-256(pk_M) || ek_K)
+//! ```
+//!
+//! where `M` is the McEliece part and `K` the ML-KEM part. `LABEL` names the
+//! parameter set, and every other field has a fixed length for it. The McEliece
+//! public key is too large to hash on every call, so its digest is kept in both
+//! the public and the secret key.
+//!
+//! All byte encodings are the McEliece part followed by the ML-KEM part; the
+//! secret key additionally ends with the digest of the McEliece public key.
+
+use std::marker::PhantomData;
+
+use ml_kem::{
+  array::typenum::Unsigned, kem::Decapsulate, EncapsulateDeterministic, Encoded, EncodedSizeUser,
+  KemCore,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+  combiner::{hybrid_kem, Component},
+  Error,
+};
+
+pub use ml_kem;
+
+const SEED_LEN: usize = 32;
+
+type Ek<K> = <K as KemCore>::EncapsulationKey;
+type Dk<K> = <K as KemCore>::DecapsulationKey;
+
+/* ml_kem only has `encapsulation_key` on the concrete decapsulation key types */
+trait Params: KemCore {
+  fn encapsulation_key(dk: &Dk<Self>) -> Ek<Self>;
+}
+
+impl Params for ml_kem::MlKem768 {
+  fn encapsulation_key(dk: &Dk<Self>) -> Ek<Self> {
+    dk.encapsulation_key().clone()
+  }
+}
+
+impl Params for ml_kem::MlKem1024 {
+  fn encapsulation_key(dk: &Dk<Self>) -> Ek<Self> {
+    dk.encapsulation_key().clone()
+  }
+}
+
+fn encoded<T: EncodedSizeUser>(bytes: &[u8]) -> Result<&Encoded<T>, Error> {
+  bytes.try_into().map_err(|_| Error::InvalidLength {
+    got: bytes.len(),
+    expected: <T::EncodedSize as Unsigned>::USIZE,
+  })
+}
+
+struct MlKem<K>(PhantomData<K>);
+
+impl<K: Params> Component for MlKem<K> {
+  type PublicKey = Ek<K>;
+  type SecretKey = Dk<K>;
+  const PUBLIC_KEY_LEN: usize = <<Ek<K> as EncodedSizeUser>::EncodedSize as Unsigned>::USIZE;
+  const SECRET_KEY_LEN: usize = <<Dk<K> as EncodedSizeUser>::EncodedSize as Unsigned>::USIZE;
+  const CIPHERTEXT_LEN: usize = <K::CiphertextSize as Unsigned>::USIZE;
+  fn public_key_bytes(pk: &Self::PublicKey) -> Vec<u8> {
+    pk.as_bytes().to_vec()
+  }
+  fn public_key_from_bytes(bytes: &[u8]) -> Result<Self::PublicKey, Error> {
+    Ok(Ek::<K>::from_bytes(encoded::<Ek<K>>(bytes)?))
+  }
+  fn secret_key_bytes(sk: &Self::SecretKey) -> Zeroizing<Vec<u8>> {
+    let mut encoded = sk.as_bytes();
+    let bytes = Zeroizing::new(encoded.to_vec());
+    encoded.zeroize();
+    bytes
+  }
+  fn secret_key_from_bytes(bytes: &[u8]) -> Result<Self::SecretKey, Error> {
+    Ok(Dk::<K>::from_bytes(encoded::<Dk<K>>(bytes)?))
+  }
+  fn public_key(sk: &Self::SecretKey) -> Self::PublicKey {
+    K::encapsulation_key(sk)
+  }
+  fn generate<F: FnMut(&mut [u8])>(mut entropy_provider: F) -> (Self::PublicKey, Self::SecretKey) {
+    let mut d = Zeroizing::new([0u8; SEED_LEN]);
+    let mut z = Zeroizing::new([0u8; SEED_LEN]);
+    entropy_provider(d.as_mut());
+    entropy_provider(z.as_mut());
+    let (dk, ek) = K::generate_deterministic(&(*d).into(), &(*z).into());
+    (ek, dk)
+  }
+  fn encapsulate<F: FnMut(&mut [u8])>(
+    pk: &Self::PublicKey,
+    ct: &mut [u8],
+    mut entropy_provider: F,
+  ) -> Zeroizing<Vec<u8>> {
+    let mut m = Zeroizing::new([0u8; SEED_LEN]);
+    entropy_provider(m.as_mut());
+    let (ct_k, ss_k) = pk.encapsulate_deterministic(&(*m).into()).unwrap();
+    ct.copy_from_slice(&ct_k);
+    Zeroizing::new(ss_k.to_vec())
+  }
+  fn decapsulate(sk: &Self::SecretKey, ct: &[u8]) -> Zeroizing<Vec<u8>> {
+    let ss_k = sk.decapsulate(ct.try_into().unwrap()).unwrap();
+    Zeroizing::new(ss_k.to_vec())
+  }
+}
+
+/// ML-KEM-768 + mceliece8192128f.
+pub mod ml_kem_768 {
+  use super::*;
+
+  hybrid_kem!(
+    component: MlKem<ml_kem::MlKem768>,
+    name: ml_kem,
+    public_key: Ek<ml_kem::MlKem768>,
+    secret_key: Dk<ml_kem::MlKem768>,
+    label: b"rmce-hybrid-ml-kem-768-v1",
+  );
+}
+
+/// ML-KEM-1024 + mceliece8192128f.
+pub mod ml_kem_1024 {
+  use super::*;
+
+  hybrid_kem!(
+    component: MlKem<ml_kem::MlKem1024>,
+    name: ml_kem,
+    public_key: Ek<ml_kem::MlKem1024>,
+    secret_key: Dk<ml_kem::MlKem1024>,
+    label: b"rmce-hybrid-ml-kem-1024-v1",
+  );
+}
@@ -1,131 +1,261 @@
 // Source: https://github.com/maandree/libkeccak
 
+// the other SHA-3 functions are only reachable through the `hash` module
+#![cfg_attr(not(feature = "hash"), allow(dead_code))]
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
 pub fn shake256(out: &mut [u8], input: &[u8]) {
-  keccak(1088, 512, input, 0x1f, out);
+  keccak(SHAKE256_RATE, input, SHAKE_SUFFIX, out);
+}
+
+pub const SHAKE128_RATE: usize = 1344;
+pub const SHAKE256_RATE: usize = 1088;
+pub const SHA3_256_RATE: usize = 1088;
+pub const SHA3_512_RATE: usize = 576;
+
+pub const SHA3_SUFFIX: u8 = 0x06;
+pub const SHAKE_SUFFIX: u8 = 0x1f;
+pub const CSHAKE_SUFFIX: u8 = 0x04;
+
+// NIST SP 800-185
+
+pub fn kmac256(out: &mut [u8], key: &[u8], input: &[u8], custom: &[u8]) {
+  let mut sponge = Sponge::kmac(SHAKE256_RATE, key, custom);
+  sponge.absorb(input);
+  sponge.absorb(&right_encode(out.len() as u64 * 8));
+  sponge.squeeze(out);
 }
 
-#[allow(non_snake_case)]
-fn keccak(r: usize, _c: u32, mut input: &[u8], sfx: u8, mut out: &mut [u8]) {
-  // init
-  let mut s = [0u8; 200];
-  let R = r / 8;
-  let mut b = 0;
-  // absorb
-  let mut in_len = input.len();
-  while in_len > 0 {
-    b = std::cmp::min(in_len, R);
-    for i in 0..b {
-      s[i] ^= input[i];
+/* SHA3-256 over the concatenation of parts, used by the hybrid combiners */
+#[cfg(any(feature = "hybrid", feature = "ml-kem"))]
+pub fn sha3_256(out: &mut [u8; 32], parts: &[&[u8]]) {
+  let mut sponge = Sponge::new(SHA3_256_RATE, SHA3_SUFFIX);
+  for part in parts {
+    sponge.absorb(part);
+  }
+  sponge.squeeze(out);
+}
+
+fn left_encode(x: u64) -> Vec<u8> {
+  let bytes = x.to_be_bytes();
+  let skip = bytes.iter().take_while(|&&b| b == 0).count().min(7);
+  let mut encoded = vec![(8 - skip) as u8];
+  encoded.extend_from_slice(&bytes[skip..]);
+  encoded
+}
+
+pub fn right_encode(x: u64) -> Vec<u8> {
+  let bytes = x.to_be_bytes();
+  let skip = bytes.iter().take_while(|&&b| b == 0).count().min(7);
+  let mut encoded = bytes[skip..].to_vec();
+  encoded.push((8 - skip) as u8);
+  encoded
+}
+
+fn encode_string(s: &[u8]) -> Vec<u8> {
+  let mut encoded = left_encode(s.len() as u64 * 8);
+  encoded.extend_from_slice(s);
+  encoded
+}
+
+fn bytepad(x: &[u8], w: usize) -> Vec<u8> {
+  let mut padded = left_encode(w as u64);
+  padded.extend_from_slice(x);
+  let len = padded.len().div_ceil(w) * w;
+  padded.resize(len, 0);
+  padded
+}
+
+fn keccak(r: usize, input: &[u8], sfx: u8, out: &mut [u8]) {
+  let mut sponge = Sponge::new(r, sfx);
+  sponge.absorb(input);
+  sponge.squeeze(out);
+}
+
+/* incremental Keccak sponge: absorb any number of times, then squeeze any number of times */
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Sponge {
+  s: [u64; 25],
+  rate: usize,
+  offset: usize,
+  sfx: u8,
+  squeezing: bool,
+}
+
+impl Sponge {
+  /* r: rate in bits, sfx: domain separation suffix with the first padding bit */
+  pub fn new(r: usize, sfx: u8) -> Self {
+    Self {
+      s: [0u64; 25],
+      rate: r / 8,
+      offset: 0,
+      sfx,
+      squeezing: false,
     }
-    input = &input[b..];
-    in_len -= b;
-    if b == R {
-      keccakf1600(&mut s);
-      b = 0;
+  }
+
+  pub fn cshake(r: usize, name: &[u8], custom: &[u8]) -> Self {
+    if name.is_empty() && custom.is_empty() {
+      return Self::new(r, SHAKE_SUFFIX);
     }
+    let mut sponge = Self::new(r, CSHAKE_SUFFIX);
+    let mut prefix = encode_string(name);
+    prefix.extend_from_slice(&encode_string(custom));
+    sponge.absorb(&bytepad(&prefix, r / 8));
+    sponge
   }
-  // pad
-  s[b] ^= sfx;
-  if (sfx & 0x80 != 0) && (b == (R - 1)) {
-    keccakf1600(&mut s);
+
+  /* the caller absorbs right_encode(output length in bits) before squeezing */
+  pub fn kmac(r: usize, key: &[u8], custom: &[u8]) -> Self {
+    let mut sponge = Self::cshake(r, b"KMAC", custom);
+    let mut padded_key = bytepad(&encode_string(key), r / 8);
+    sponge.absorb(&padded_key);
+    padded_key.zeroize();
+    sponge
   }
-  s[R - 1] ^= 0x80;
-  keccakf1600(&mut s);
-  // squeeze
-  let mut out_len = out.len();
-  while out_len > 0 {
-    b = std::cmp::min(out_len, R);
-    for i in 0..b {
-      out[i] = s[i];
-    }
-    out = &mut out[b..];
-    out_len -= b;
-    if out_len > 0 {
-      keccakf1600(&mut s);
+
+  pub fn absorb(&mut self, mut input: &[u8]) {
+    assert!(!self.squeezing, "absorbing after squeezing has started");
+    while !input.is_empty() {
+      let b = std::cmp::min(input.len(), self.rate - self.offset);
+      xor_bytes(&mut self.s, self.offset, &input[..b]);
+      input = &input[b..];
+      self.offset += b;
+      if self.offset == self.rate {
+        keccakf1600(&mut self.s);
+        self.offset = 0;
+      }
     }
   }
-}
 
-fn lfsr86540(rr: &mut u8) -> bool {
-  let r = *rr;
-  let c = (r & 0x80) >> 7;
-  *rr = (r << 1) ^ (c * 0x71);
-  (*rr & 2) >> 1 != 0
-}
+  fn pad(&mut self) {
+    let r = self.rate;
+    xor_bytes(&mut self.s, self.offset, &[self.sfx]);
+    if (self.sfx & 0x80 != 0) && (self.offset == (r - 1)) {
+      keccakf1600(&mut self.s);
+    }
+    xor_bytes(&mut self.s, r - 1, &[0x80]);
+    keccakf1600(&mut self.s);
+    self.offset = 0;
+    self.squeezing = true;
+  }
 
-fn rol(a: u64, o: u32) -> u64 {
-  (a << o) ^ (a >> (64 - o))
+  pub fn squeeze(&mut self, mut out: &mut [u8]) {
+    if !self.squeezing {
+      self.pad();
+    }
+    while !out.is_empty() {
+      if self.offset == self.rate {
+        keccakf1600(&mut self.s);
+        self.offset = 0;
+      }
+      let b = std::cmp::min(out.len(), self.rate - self.offset);
+      read_bytes(&self.s, self.offset, &mut out[..b]);
+      out = &mut out[b..];
+      self.offset += b;
+    }
+  }
 }
 
-fn xor64(x: &mut [u8; 8], mut u: u64) {
-  for i in 0..8 {
-    x[i] ^= (u & 0xff) as u8;
-    u >>= 8;
+/* xors data into the state, starting at byte offset pos */
+fn xor_bytes(s: &mut [u64; 25], mut pos: usize, mut data: &[u8]) {
+  while !pos.is_multiple_of(8) && !data.is_empty() {
+    s[pos / 8] ^= (data[0] as u64) << (8 * (pos % 8));
+    pos += 1;
+    data = &data[1..];
+  }
+  let mut words = data.chunks_exact(8);
+  for word in &mut words {
+    s[pos / 8] ^= u64::from_le_bytes(word.try_into().unwrap());
+    pos += 8;
+  }
+  for &byte in words.remainder() {
+    s[pos / 8] ^= (byte as u64) << (8 * (pos % 8));
+    pos += 1;
   }
 }
 
-fn r_l(x: usize, y: usize, s: &[u8; 200]) -> u64 {
-  let off = 8 * (x + 5 * y);
-  u64::from_le_bytes(s[off..off + 8].try_into().unwrap())
+/* copies state bytes, starting at byte offset pos, into out */
+fn read_bytes(s: &[u64; 25], pos: usize, out: &mut [u8]) {
+  for (i, byte) in out.iter_mut().enumerate() {
+    let p = pos + i;
+    *byte = (s[p / 8] >> (8 * (p % 8))) as u8;
+  }
 }
 
-fn w_l(x: usize, y: usize, l: u64, s: &mut [u8; 200]) {
-  let off = 8 * (x + 5 * y);
-  s[off..off + 8].copy_from_slice(&l.to_le_bytes());
-}
+const ROUND_CONSTANTS: [u64; 24] = [
+  0x0000000000000001,
+  0x0000000000008082,
+  0x800000000000808a,
+  0x8000000080008000,
+  0x000000000000808b,
+  0x0000000080000001,
+  0x8000000080008081,
+  0x8000000000008009,
+  0x000000000000008a,
+  0x0000000000000088,
+  0x0000000080008009,
+  0x000000008000000a,
+  0x000000008000808b,
+  0x800000000000008b,
+  0x8000000000008089,
+  0x8000000000008003,
+  0x8000000000008002,
+  0x8000000000000080,
+  0x000000000000800a,
+  0x800000008000000a,
+  0x8000000080008081,
+  0x8000000000008080,
+  0x0000000080000001,
+  0x8000000080008008,
+];
 
-fn x_l(x: usize, y: usize, l: u64, s: &mut [u8; 200]) {
-  let off = 8 * (x + 5 * y);
-  let ss = (&mut s[off..off + 8]).try_into().unwrap();
-  xor64(ss, l)
-}
+/* rotation offsets and destination lanes of the combined ρπ step, */
+/* following lane 1 along the π cycle */
+const RHO: [u32; 24] = [
+  1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const PI: [usize; 24] = [
+  10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
 
-#[allow(non_snake_case)]
-fn keccakf1600(s: &mut [u8; 200]) {
-  let mut R = 0x01u8;
-  let mut C = [0u64; 5];
-  let mut D;
-  let mut Y;
-  for _ in 0..24 {
+/* lane (x, y) is a[x + 5 * y] */
+fn keccakf1600(a: &mut [u64; 25]) {
+  for rc in ROUND_CONSTANTS {
     // Θ
+    let mut c = [0u64; 5];
     for x in 0..5 {
-      C[x] = r_l(x, 0, s) ^ r_l(x, 1, s) ^ r_l(x, 2, s) ^ r_l(x, 3, s) ^ r_l(x, 4, s);
+      c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
     }
     for x in 0..5 {
-      D = C[(x + 4) % 5] ^ rol(C[(x + 1) % 5], 1);
+      let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
       for y in 0..5 {
-        x_l(x, y, D, s);
+        a[x + 5 * y] ^= d;
       }
     }
     // ρπ
-    let mut x = 1;
-    let mut y = 0;
-    let mut r = 0;
-    D = r_l(x, y, s);
-    for j in 0..24 {
-      r += j + 1;
-      Y = (2 * x + 3 * y) % 5;
-      x = y;
-      y = Y;
-      C[0] = r_l(x, y, s);
-      w_l(x, y, rol(D, r % 64), s);
-      D = C[0];
+    let mut last = a[1];
+    for i in 0..24 {
+      let tmp = a[PI[i]];
+      a[PI[i]] = last.rotate_left(RHO[i]);
+      last = tmp;
     }
-    // X
+    // Χ
     for y in 0..5 {
+      let row = [
+        a[5 * y],
+        a[5 * y + 1],
+        a[5 * y + 2],
+        a[5 * y + 3],
+        a[5 * y + 4],
+      ];
       for x in 0..5 {
-        C[x] = r_l(x, y, s);
-      }
-      for x in 0..5 {
-        w_l(x, y, C[x] ^ ((!C[(x + 1) % 5]) & C[(x + 2) % 5]), s);
+        a[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
       }
     }
     // ι
-    for j in 0..7 {
-      if lfsr86540(&mut R) {
-        x_l(0, 0, 1 << ((1 << j) - 1), s);
-      }
-    }
+    a[0] ^= rc;
   }
 }
 
@@ -140,4 +270,33 @@ mod tests {
     shake256(result, message);
     assert_eq!(expected, result);
   }
+
+  // NIST SP 800-185 samples
+
+  #[test]
+  fn cshake256_sample() {
+    let data = [0u8, 1, 2, 3];
+    let expected = b"\xd0\x08\x82\x8e\x2b\x80\xac\x9d\x22\x18\xff\xee\x1d\x07\x0c\x48\xb8\xe4\xc8\x7b\xff\x32\xc9\x69\x9d\x5b\x68\x96\xee\xe0\xed\xd1\x64\x02\x0e\x2b\xe0\x56\x08\x58\xd9\xc0\x0c\x03\x7e\x34\xa9\x69\x37\xc5\x61\xa7\x4c\x41\x2b\xb4\xc7\x46\x46\x95\x27\x28\x1c\x8c";
+    let result = &mut [0u8; 64];
+    let mut sponge = Sponge::cshake(SHAKE256_RATE, b"", b"Email Signature");
+    sponge.absorb(&data);
+    sponge.squeeze(result);
+    assert_eq!(expected, result);
+  }
+
+  #[test]
+  fn kmac256_samples() {
+    let key: Vec<u8> = (0x40..0x60).collect();
+    let result = &mut [0u8; 64];
+
+    let data = [0u8, 1, 2, 3];
+    let expected = b"\x20\xc5\x70\xc3\x13\x46\xf7\x03\xc9\xac\x36\xc6\x1c\x03\xcb\x64\xc3\x97\x0d\x0c\xfc\x78\x7e\x9b\x79\x59\x9d\x27\x3a\x68\xd2\xf7\xf6\x9d\x4c\xc3\xde\x9d\x10\x4a\x35\x16\x89\xf2\x7c\xf6\xf5\x95\x1f\x01\x03\xf3\x3f\x4f\x24\x87\x10\x24\xd9\xc2\x77\x73\xa8\xdd";
+    kmac256(result, &key, &data, b"My Tagged Application");
+    assert_eq!(expected, result);
+
+    let data: Vec<u8> = (0..200).collect();
+    let expected = b"\x75\x35\x8c\xf3\x9e\x41\x49\x4e\x94\x97\x07\x92\x7c\xee\x0a\xf2\x0a\x3f\xf5\x53\x90\x4c\x86\xb0\x8f\x21\xcc\x41\x4b\xcf\xd6\x91\x58\x9d\x27\xcf\x5e\x15\x36\x9c\xbb\xff\x8b\x9a\x4c\x2e\xb1\x78\x00\x85\x5d\x02\x35\xff\x63\x5d\xa8\x25\x33\xec\x6b\x75\x9b\x69";
+    kmac256(result, &key, &data, b"");
+    assert_eq!(expected, result);
+  }
 }
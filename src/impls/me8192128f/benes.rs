@@ -16,6 +16,7 @@ use super::{
 /*        bits, condition bits of the Benes network */
 /*        rev, 0 for normal application; !0 for inverse */
 /* output: r, permuted bits */
+#[allow(clippy::identity_op, clippy::needless_range_loop)]
 pub fn apply_benes(r: &mut [u8; (1 << GFBITS) / 8], bits: &[u8; COND_BYTES], rev: bool) {
   let mut r_int_v = [[0u64; 64]; 2];
   let mut r_int_h = [[0u64; 64]; 2];
@@ -85,14 +86,14 @@ pub fn apply_benes(r: &mut [u8; (1 << GFBITS) / 8], bits: &[u8; COND_BYTES], rev
   transpose_64x64(&mut r_int_v[1], &r_int_h[1]);
 
   for i in 0..64 {
-    store8(r.as_mut_array(i * 16 + 0), r_int_v[0][i]);
-    store8(r.as_mut_array(i * 16 + 8), r_int_v[1][i]);
+    store8(r.as_mut_array_at(i * 16 + 0), r_int_v[0][i]);
+    store8(r.as_mut_array_at(i * 16 + 8), r_int_v[1][i]);
   }
 }
 
 /* input: condition bits c */
 /* output: support s */
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::needless_range_loop)]
 pub fn support_gen(s: &mut [Gf; SYS_N], c: &[u8; COND_BYTES]) {
   // pregenerate L
   // lazy_static::lazy_static! {
@@ -128,6 +129,7 @@ pub fn support_gen(s: &mut [Gf; SYS_N], c: &[u8; COND_BYTES]) {
 }
 
 /* middle layers of the benes network */
+#[allow(clippy::identity_op)]
 fn layer_in(data: &mut [[u64; 64]; 2], bits: &[u64; 64], lgs: i32) {
   let mut d;
 
@@ -152,6 +154,7 @@ fn layer_in(data: &mut [[u64; 64]; 2], bits: &[u64; 64], lgs: i32) {
   }
 }
 /* first and last layers of the benes network */
+#[allow(clippy::identity_op)]
 fn layer_ex(data: &mut [[u64; 64]; 2], bits: &[u64; 64], lgs: i32) {
   let mut d;
 
@@ -8,7 +8,7 @@ use super::{gf::Gf, params::SYS_T};
 /* the Berlekamp-Massey algorithm */
 /* input: s, sequence of field elements */
 /* output: out, minimal polynomial of s */
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::manual_memcpy)]
 pub fn bm(out: &mut [Gf; SYS_T + 1], s: &[Gf; 2 * SYS_T]) {
   let mut L = 0;
   let mut mle;
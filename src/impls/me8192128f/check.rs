@@ -0,0 +1,157 @@
+/*
+  This file is for checking the consistency of keys
+
+  Unlike the rest of the implementation, these checks run in variable time
+*/
+
+use boxed_array::from_default;
+
+use super::{
+  controlbits::{control_bits_from_permutation, permutation_from_control_bits},
+  gf::Gf,
+  params::{COND_BYTES, GFBITS, IRR_BYTES, PK_NROWS, SYND_BYTES, SYS_T},
+  util::{load8, load_gf, AsRefArray},
+  SECRET_KEY_LEN,
+};
+
+/* input: ciphertext c */
+/* return: true if the padding bits of the last byte are zero */
+/* (PK_NROWS is a multiple of 8 here, so there are none and this always holds) */
+pub fn ct_check(c: &[u8; SYND_BYTES]) -> bool {
+  let tail = PK_NROWS % 8;
+  if tail == 0 {
+    return true;
+  }
+  c[SYND_BYTES - 1] & (0xFFu8 << tail) == 0
+}
+
+/* input: secret key sk */
+/* return: true if the pivots, the Goppa polynomial and the control bits are well-formed */
+pub fn sk_check(sk: &[u8; SECRET_KEY_LEN]) -> bool {
+  pivots_check(load8(sk.as_ref_array(32)))
+    && irr_check(sk.as_ref_array(40))
+    && cond_check(sk.as_ref_array(40 + IRR_BYTES))
+}
+
+/* return: true if 32 of the 64 columns of the block are marked as pivots */
+/* (only the count: the field records which columns keygen swapped, and the */
+/* permutation before the swap is not kept, so the columns cannot be checked) */
+fn pivots_check(pivots: u64) -> bool {
+  pivots.count_ones() == 32
+}
+
+/* input: coefficients of the monic Goppa polynomial g, leading 1 omitted */
+/* return: true if every coefficient is a field element and g is irreducible */
+#[allow(clippy::needless_range_loop)]
+fn irr_check(irr: &[u8; IRR_BYTES]) -> bool {
+  let mut g = [Gf(0); SYS_T + 1];
+
+  for i in 0..SYS_T {
+    let raw = u16::from_le_bytes(*irr.as_ref_array(i * 2));
+    if raw >> GFBITS != 0 {
+      return false;
+    }
+    g[i] = load_gf(irr.as_ref_array(i * 2));
+  }
+  g[SYS_T] = Gf(1);
+
+  is_irreducible(&g)
+}
+
+/* input: control bits c */
+/* return: true if c are the canonical control bits of the permutation they encode */
+/* (any c encodes some permutation, so this only catches part of the corruptions) */
+fn cond_check(c: &[u8; COND_BYTES]) -> bool {
+  let mut pi: Box<[i16; 1 << GFBITS]> = from_default();
+  let mut c_cmp: Box<[u8; COND_BYTES]> = from_default();
+
+  permutation_from_control_bits(&mut pi, c);
+  control_bits_from_permutation(&mut c_cmp, &pi);
+
+  c[..] == c_cmp[..]
+}
+
+/* Rabin's test: since t is a power of 2, g of degree t is irreducible */
+/* iff x^(q^t) = x mod g and gcd(x^(q^(t/2)) - x, g) = 1, where q = 2^m */
+fn is_irreducible(g: &[Gf; SYS_T + 1]) -> bool {
+  let mut a = [Gf(0); SYS_T];
+  a[1] = Gf(1);
+
+  for _ in 0..SYS_T / 2 {
+    frobenius(&mut a, g);
+  }
+
+  let mut h = a;
+  h[1] = h[1].add(Gf(1));
+  if !gcd_is_one(&h, g) {
+    return false;
+  }
+
+  for _ in SYS_T / 2..SYS_T {
+    frobenius(&mut a, g);
+  }
+
+  let mut x = [Gf(0); SYS_T];
+  x[1] = Gf(1);
+  a == x
+}
+
+/* a = a^q mod g */
+fn frobenius(a: &mut [Gf; SYS_T], g: &[Gf; SYS_T + 1]) {
+  for _ in 0..GFBITS {
+    sq_mod(a, g);
+  }
+}
+
+/* a = a^2 mod g */
+fn sq_mod(a: &mut [Gf; SYS_T], g: &[Gf; SYS_T + 1]) {
+  let mut prod = [Gf(0); SYS_T * 2 - 1];
+
+  for i in 0..SYS_T {
+    prod[i * 2] = a[i].mul(a[i]);
+  }
+
+  for i in (SYS_T..=(SYS_T - 1) * 2).rev() {
+    let c = prod[i];
+    for j in 0..SYS_T {
+      prod[i - SYS_T + j] = prod[i - SYS_T + j].add(c.mul(g[j]));
+    }
+  }
+
+  a.copy_from_slice(&prod[..SYS_T]);
+}
+
+/* return: true if gcd(a, g) is a nonzero constant */
+fn gcd_is_one(a: &[Gf; SYS_T], g: &[Gf; SYS_T + 1]) -> bool {
+  let mut r0 = g.to_vec();
+  let mut r1 = a.to_vec();
+  trim(&mut r1);
+
+  while !r1.is_empty() {
+    rem(&mut r0, &r1);
+    std::mem::swap(&mut r0, &mut r1);
+  }
+
+  r0.len() == 1
+}
+
+/* a = a mod b, b has nonzero leading coefficient */
+fn rem(a: &mut Vec<Gf>, b: &[Gf]) {
+  let lead_inv = b[b.len() - 1].inv();
+  trim(a);
+  while a.len() >= b.len() {
+    let shift = a.len() - b.len();
+    let c = a[a.len() - 1].mul(lead_inv);
+    for j in 0..b.len() {
+      a[shift + j] = a[shift + j].add(c.mul(b[j]));
+    }
+    trim(a);
+  }
+}
+
+/* drop zero leading coefficients */
+fn trim(a: &mut Vec<Gf>) {
+  while a.last() == Some(&Gf(0)) {
+    a.pop();
+  }
+}
@@ -2,6 +2,8 @@
 /* See David Nassimi, Sartaj Sahni "Parallel algorithms to set up the Benes permutationnetwork" */
 /* See also https://cr.yp.to/papers/controlbits-20200923.pdf */
 
+use boxed_array::from_default;
+
 use crate::impls::{
   int32_sort,
   subroutines::{crypto_declassify::crypto_declassify, crypto_int::CryptoInt},
@@ -19,26 +21,14 @@ pub fn control_bits_from_permutation(
 ) {
   const N: usize = 1<<GFBITS;
   let mut temp = vec![0i32; 2 * N];
-  let mut pi_test = vec![0i16; N];
+  let mut pi_test: Box<[i16; N]> = from_default();
   loop {
     out.fill(0);
     cbrecursion(out, 0, 1, pi, GFBITS, N, &mut temp);
 
     // check for correctness
 
-    for i in 0..N {
-      pi_test[i] = i as i16;
-    }
-    let mut ooff = 0;
-    for i in 0..GFBITS {
-      layer(&mut pi_test, &out[ooff..], i, N);
-      ooff += N >> 4;
-    }
-
-    for i in (0..=GFBITS - 2).rev() {
-      layer(&mut pi_test, &out[ooff..], i, N);
-      ooff += N >> 4;
-    }
+    permutation_from_control_bits(&mut pi_test, out);
 
     let mut diff = 0;
     for i in 0..N {
@@ -53,13 +43,33 @@ pub fn control_bits_from_permutation(
   }
 }
 
+/* input: control bits cb produced by control_bits_from_permutation */
+/* output: pi, the permutation encoded by cb */
+#[allow(clippy::needless_range_loop)]
+pub fn permutation_from_control_bits(pi: &mut [i16; 1 << GFBITS], cb: &[u8; COND_BYTES]) {
+  const N: usize = 1 << GFBITS;
+  for i in 0..N {
+    pi[i] = i as i16;
+  }
+  let mut ooff = 0;
+  for i in 0..GFBITS {
+    layer(pi, &cb[ooff..], i, N);
+    ooff += N >> 4;
+  }
+
+  for i in (0..=GFBITS - 2).rev() {
+    layer(pi, &cb[ooff..], i, N);
+    ooff += N >> 4;
+  }
+}
+
 /* parameters: 1 <= w <= 14; n = 2^w */
 /* input: permutation pi of {0,1,...,n-1} */
 /* output: (2m-1)n/2 control bits at positions pos,pos+step,... */
 /* output position pos is by definition 1&(out[pos/8]>>(pos&7)) */
 /* caller must 0-initialize positions first */
 /* temp must have space for int32[2*n] */
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::manual_slice_size_calculation)]
 fn cbrecursion(
   out: &mut [u8],
   mut pos: usize,
@@ -246,7 +256,7 @@ fn layer(p: &mut [i16], cb: &[u8], s: usize, n: usize) {
       d = p[ i+j ] ^ p[ i+j+stride ];
       m = ((cb[ index >> 3 ] >> (index & 7)) & 1) as i16;
       m = m.wrapping_neg();
-      d &= m as i16;
+      d &= m;
       p[ i+j ] ^= d;
       p[ i+j+stride ] ^= d;
       index += 1;
@@ -3,6 +3,7 @@
 */
 
 use boxed_array::from_default;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{
   benes::support_gen,
@@ -10,28 +11,68 @@ use super::{
   gf::Gf,
   params::{COND_BYTES, IRR_BYTES, SYND_BYTES, SYS_N, SYS_T},
   root::root,
-  synd::synd,
+  synd::{synd, synd_inv},
   util::{load_gf, AsRefArray},
 };
 
+/* values derived from the secret key which do not depend on the ciphertext */
+#[allow(non_snake_case)]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DecryptTables {
+  g: [Gf; SYS_T + 1],
+  L: Box<[Gf; SYS_N]>,
+  inv: Box<[Gf; SYS_N]>,
+}
+
+impl DecryptTables {
+  /* input: sk, secret key */
+  /* output: Goppa polynomial g, support L and 1/g(a)^2 for a in L */
+  pub fn new(sk: &[u8; IRR_BYTES + COND_BYTES]) -> Self {
+    let mut tables = Self {
+      g: [Gf(0); SYS_T + 1],
+      L: from_default(),
+      inv: from_default(),
+    };
+
+    for i in 0..SYS_T {
+      tables.g[i] = load_gf(sk.as_ref_array(i * 2));
+    }
+    tables.g[SYS_T] = Gf(1);
+
+    support_gen(&mut tables.L, sk.as_ref_array(IRR_BYTES));
+
+    synd_inv(&mut tables.inv, &tables.g, &tables.L);
+
+    tables
+  }
+}
+
 /* Niederreiter decryption with the Berlekamp decoder */
 /* intput: sk, secret key */
 /*         c, ciphertext */
 /* output: e, error vector */
 /* return: 0 for success; 1 for failure */
-#[allow(non_snake_case)]
 pub fn decrypt(
   e: &mut [u8; SYS_N / 8],
   sk: &[u8; IRR_BYTES + COND_BYTES],
   c: &[u8; SYND_BYTES],
+) -> u16 {
+  decrypt_with_tables(e, &DecryptTables::new(sk), c)
+}
+
+/* same as decrypt, but with the secret key already expanded */
+#[allow(non_snake_case)]
+pub fn decrypt_with_tables(
+  e: &mut [u8; SYS_N / 8],
+  tables: &DecryptTables,
+  c: &[u8; SYND_BYTES],
 ) -> u16 {
   let mut w = 0;
   let mut check: u16;
 
   let mut r: Box<[u8; SYS_N / 8]> = from_default();
 
-  let mut g = [Gf(0); SYS_T + 1];
-  let mut L: Box<[Gf; SYS_N]> = from_default();
+  let L = &tables.L;
 
   let mut s = [Gf(0); SYS_T * 2];
   let mut s_cmp = [Gf(0); SYS_T * 2];
@@ -44,18 +85,11 @@ pub fn decrypt(
     r[i] = c[i];
   }
 
-  for i in 0..SYS_T {
-    g[i] = load_gf(sk.as_ref_array(i*2));
-  }
-  g[SYS_T] = Gf(1);
-
-  support_gen(&mut L, sk.as_ref_array(IRR_BYTES));
-
-  synd(&mut s, &g, &L, &r);
+  synd(&mut s, &tables.inv, L, &r);
 
   bm(&mut locator, &s);
 
-  root(&mut images, &locator, &L);
+  root(&mut images, &locator, L);
 
   //
 
@@ -68,7 +102,7 @@ pub fn decrypt(
     w += t;
   }
 
-  synd(&mut s_cmp, &g, &L, e);
+  synd(&mut s_cmp, &tables.inv, L, e);
 
   //
 
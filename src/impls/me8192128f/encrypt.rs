@@ -42,6 +42,7 @@ fn same_mask(x: u16, y: u16) -> u8 {
 }
 
 /* output: e, an error vector of weight t */
+#[allow(clippy::needless_range_loop)]
 fn gen_e<F: FnMut(&mut [u8])>(e: &mut [u8; SYS_N / 8], mut random_bytes_generator: F) {
   let mut ind = [Gf(0); SYS_T];
   let mut bytes = [0u8; SYS_T * 2];
@@ -7,6 +7,8 @@ use super::params::{GFBITS, GFMASK, SYS_T};
 #[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Gf(pub u16);
 
+impl zeroize::DefaultIsZeroes for Gf {}
+
 impl Gf {
   pub fn is_zero(self) -> Self {
     let mut t = self.0 as u32;
@@ -47,6 +49,7 @@ impl Gf {
   pub fn inv(self) -> Self {
     self.frac(Gf(1))
   }
+  #[allow(clippy::needless_range_loop)]
   fn sq2(self) -> Self {
     const B: [u64; 4] = [
       0x1111111111111111,
@@ -74,6 +77,7 @@ impl Gf {
     }
     Self((x & GFMASK) as u16)
   }
+  #[allow(clippy::needless_range_loop)]
   fn sqmul(self, other: Self) -> Self {
     const M: [u64; 3] = [0x0000001FF0000000, 0x000000000FF80000, 0x000000000007E000];
 
@@ -99,6 +103,7 @@ impl Gf {
 
     Self((x & GFMASK) as u16)
   }
+  #[allow(clippy::needless_range_loop)]
   fn sq2mul(self, other: Self) -> Self {
     const M: [u64; 6] = [
       0x1FF0000000000000,
@@ -133,6 +138,7 @@ impl Gf {
   }
 }
 
+#[allow(clippy::identity_op, clippy::manual_memcpy)]
 pub fn gf_mul(out: &mut [Gf; SYS_T], in0: &[Gf; SYS_T], in1: &[Gf; SYS_T]) {
   let mut prod = [Gf(0); SYS_T * 2 - 1];
   for i in 0..SYS_T {
@@ -9,6 +9,7 @@
 
 mod benes;
 mod bm;
+mod check;
 mod controlbits;
 mod decrypt;
 mod encrypt;
@@ -26,3 +27,4 @@ use self::params::{PK_ROW_BYTES, PK_NROWS, SYND_BYTES, SYS_N, COND_BYTES, IRR_BY
 pub const PUBLIC_KEY_LEN: usize = PK_NROWS*PK_ROW_BYTES;
 pub const SECRET_KEY_LEN: usize = 40 + IRR_BYTES + COND_BYTES + SYS_N/8;
 pub const CIPHER_TEXT_LEN: usize = SYND_BYTES;
+pub const SHARED_KEY_LEN: usize = 32;
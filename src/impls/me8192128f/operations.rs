@@ -1,9 +1,10 @@
 use boxed_array::from_default;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::impls::{
-  libkeccak::shake256,
+  libkeccak::{shake256, Sponge, SHAKE256_RATE, SHAKE_SUFFIX},
   me8192128f::{
-    controlbits::control_bits_from_permutation,
+    controlbits::{control_bits_from_permutation, permutation_from_control_bits},
     pk_gen::pk_gen,
     sk_gen::genpoly_gen,
     util::{load4, load_gf, store8, store_gf},
@@ -11,10 +12,11 @@ use crate::impls::{
 };
 
 use super::{
-  decrypt::decrypt,
+  check::{ct_check, sk_check},
+  decrypt::{decrypt, decrypt_with_tables, DecryptTables},
   encrypt::encrypt,
   gf::Gf,
-  params::{COND_BYTES, GFBITS, IRR_BYTES, SYND_BYTES, SYS_N, SYS_T},
+  params::{COND_BYTES, GFBITS, IRR_BYTES, SYS_N, SYS_T},
   util::{AsMutArray, AsRefArray},
   CIPHER_TEXT_LEN, PUBLIC_KEY_LEN, SECRET_KEY_LEN,
 };
@@ -26,48 +28,85 @@ pub fn crypto_kem_enc<F: FnMut(&mut [u8])>(
   random_bytes_generator: F,
 ) {
   let mut e: Box<[u8; SYS_N / 8]> = from_default();
-  let mut one_ec = vec![1u8; 1 + SYS_N / 8 + SYND_BYTES].into_boxed_slice();
 
   encrypt(c, pk, &mut e, random_bytes_generator);
 
-  one_ec[1..1 + SYS_N / 8].copy_from_slice(e.as_ref());
-  one_ec[1 + SYS_N / 8..].copy_from_slice(c);
+  // key = H(1, e, c)
 
-  shake256(key, &one_ec);
+  let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+  hash.absorb(&[1]);
+  hash.absorb(e.as_ref());
+  hash.absorb(c);
+  hash.squeeze(key);
 }
 
 pub fn crypto_kem_dec(key: &mut [u8], c: &[u8; CIPHER_TEXT_LEN], sk: &[u8; SECRET_KEY_LEN]) {
   let mut e: Box<[u8; SYS_N / 8]> = from_default();
-  let mut preimage = vec![0u8; 1 + SYS_N / 8 + SYND_BYTES].into_boxed_slice();
-  let s = &sk[40 + IRR_BYTES + COND_BYTES..];
+  let s = sk.as_ref_array(40 + IRR_BYTES + COND_BYTES);
 
   let ret_decrypt = decrypt(&mut e, sk.as_ref_array(40), c);
 
+  kem_dec_finish(key, c, &e, s, ret_decrypt);
+}
+
+/* secret key with the decryption tables precomputed */
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct ExpandedSecretKey {
+  tables: DecryptTables,
+  s: Box<[u8; SYS_N / 8]>,
+}
+
+pub fn crypto_kem_sk_expand(sk: &[u8; SECRET_KEY_LEN]) -> ExpandedSecretKey {
+  let mut s: Box<[u8; SYS_N / 8]> = from_default();
+  s.copy_from_slice(&sk[40 + IRR_BYTES + COND_BYTES..]);
+  ExpandedSecretKey {
+    tables: DecryptTables::new(sk.as_ref_array(40)),
+    s,
+  }
+}
+
+pub fn crypto_kem_dec_expanded(key: &mut [u8], c: &[u8; CIPHER_TEXT_LEN], sk: &ExpandedSecretKey) {
+  let mut e: Box<[u8; SYS_N / 8]> = from_default();
+
+  let ret_decrypt = decrypt_with_tables(&mut e, &sk.tables, c);
+
+  kem_dec_finish(key, c, &e, &sk.s, ret_decrypt);
+}
+
+/* hashes either the error vector or s (on decryption failure) together with c */
+fn kem_dec_finish(
+  key: &mut [u8],
+  c: &[u8; CIPHER_TEXT_LEN],
+  e: &[u8; SYS_N / 8],
+  s: &[u8; SYS_N / 8],
+  ret_decrypt: u16,
+) {
   let mut m = ret_decrypt;
   m = m.wrapping_sub(1);
   m >>= 8;
   let m = m as u8;
 
-  let mut offset = 0;
-  preimage[offset] = m & 1;
-  offset += 1;
-  for i in 0..SYS_N / 8 {
-    preimage[offset] = (!m & s[i]) | (m & e[i]);
-    offset += 1;
+  let mut selected = [0u8; SYS_N / 8];
+  for (out, (&s, &e)) in selected.iter_mut().zip(s.iter().zip(e)) {
+    *out = (!m & s) | (m & e);
   }
 
-  for i in 0..SYND_BYTES {
-    preimage[offset] = c[i];
-    offset += 1;
-  }
+  // key = H(1, e, c) on success, H(0, s, c) on failure
 
-  shake256(key, &preimage);
+  let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+  hash.absorb(&[m & 1]);
+  hash.absorb(&selected);
+  hash.absorb(c);
+  hash.squeeze(key);
+
+  selected.zeroize();
 }
 
 const SIZE_OF_R: usize = SYS_N / 8 + (1 << GFBITS) * std::mem::size_of::<u32>() + SYS_T * 2 + 32;
 const LEN_OF_PERM: usize = 1 << GFBITS;
 const SIZE_OF_PERM: usize = LEN_OF_PERM * std::mem::size_of::<u32>();
 
+#[allow(clippy::needless_range_loop)]
 pub fn crypto_kem_keypair<F: FnMut(&mut [u8])>(
   pk: &mut [u8; PUBLIC_KEY_LEN],
   sk: &mut [u8; SECRET_KEY_LEN],
@@ -107,7 +146,7 @@ pub fn crypto_kem_keypair<F: FnMut(&mut [u8])>(
     }
 
     for i in 0..SYS_T {
-      store_gf(sk.as_mut_array(skoffset + i * 2), irr[i]);
+      store_gf(sk.as_mut_array_at(skoffset + i * 2), irr[i]);
     }
 
     // generating permutation
@@ -125,7 +164,7 @@ pub fn crypto_kem_keypair<F: FnMut(&mut [u8])>(
     }
 
     skoffset += IRR_BYTES;
-    control_bits_from_permutation(sk.as_mut_array(skoffset), &*pi);
+    control_bits_from_permutation(sk.as_mut_array_at(skoffset), &pi);
     skoffset += COND_BYTES;
 
     // storing the random string s
@@ -135,7 +174,35 @@ pub fn crypto_kem_keypair<F: FnMut(&mut [u8])>(
 
     // storing positions of the 32 pivots
 
-    store8(sk.as_mut_array(32), pivots);
+    store8(sk.as_mut_array_at(32), pivots);
     break;
   }
 }
+
+/* input: secret key sk */
+/* output: public key pk, rebuilt from g and the permutation encoded by the control bits */
+/* return: false if sk does not produce a systematic public key */
+pub fn crypto_kem_pk_from_sk(pk: &mut [u8; PUBLIC_KEY_LEN], sk: &[u8; SECRET_KEY_LEN]) -> bool {
+  let mut perm: Box<[u32; LEN_OF_PERM]> = from_default();
+  let mut pi: Box<[i16; LEN_OF_PERM]> = from_default();
+
+  permutation_from_control_bits(&mut pi, sk.as_ref_array(40 + IRR_BYTES));
+
+  // pk_gen sorts perm to obtain pi, so feed it the inverse permutation
+
+  for i in 0..LEN_OF_PERM {
+    perm[pi[i] as u16 as usize] = i as u32;
+  }
+
+  let mut pivots = 0;
+
+  pk_gen(pk, sk.as_ref_array(40), &perm, &mut pi, &mut pivots)
+}
+
+pub fn crypto_kem_sk_check(sk: &[u8; SECRET_KEY_LEN]) -> bool {
+  sk_check(sk)
+}
+
+pub fn crypto_kem_ct_check(c: &[u8; CIPHER_TEXT_LEN]) -> bool {
+  ct_check(c)
+}
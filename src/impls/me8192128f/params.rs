@@ -7,8 +7,10 @@ pub const IRR_BYTES: usize = SYS_T * 2;
 
 pub const PK_NROWS: usize = SYS_T * GFBITS;
 pub const PK_NCOLS: usize = SYS_N - PK_NROWS;
+#[allow(clippy::manual_div_ceil)]
 pub const PK_ROW_BYTES: usize = (PK_NCOLS + 7) / 8;
 
+#[allow(clippy::manual_div_ceil)]
 pub const SYND_BYTES: usize = (PK_NROWS + 7) / 8;
 
 pub const GFMASK: u64 = (1 << GFBITS) - 1;
@@ -23,7 +23,12 @@ use super::{
 
 /* input: secret key sk */
 /* output: public key pk */
-#[allow(non_snake_case)]
+#[allow(
+  non_snake_case,
+  clippy::identity_op,
+  clippy::manual_div_ceil,
+  clippy::needless_range_loop
+)]
 pub fn pk_gen(
   pk: &mut [u8; PUBLIC_KEY_LEN],
   sk: &[u8; SYS_T * 2],
@@ -47,7 +52,6 @@ pub fn pk_gen(
   for i in 0..SYS_T {
     g[i] = load_gf(sk.as_ref_array(i * 2));
   }
-  drop(sk);
 
   for i in 0..(1 << GFBITS) {
     buf[i] = perm[i] as u64;
@@ -113,10 +117,8 @@ pub fn pk_gen(
       if row >= PK_NROWS {
         break;
       }
-      if row == PK_NROWS - 32 {
-        if !mov_columns(&mut *mat, pi, pivots) {
-          return false;
-        }
+      if row == PK_NROWS - 32 && !mov_columns(&mut mat, pi, pivots) {
+        return false;
       }
 
       for k in row + 1..PK_NROWS {
@@ -182,6 +184,7 @@ fn same_mask(x: u16, y: u16) -> u64 {
   mask
 }
 
+#[allow(clippy::needless_range_loop)]
 fn mov_columns(mat: &mut [[u8; SYS_N / 8]; PK_NROWS], pi: &mut [i16], pivots: &mut u64) -> bool {
   const ONE: u64 = 1;
   let mut buf = [0u64; 64];
@@ -250,7 +253,7 @@ fn mov_columns(mat: &mut [[u8; SYS_N / 8]; PK_NROWS], pi: &mut [i16], pivots: &m
       t ^= d << j;
     }
 
-    store8(mat[i].as_mut_array(BLOCK_IDX), t);
+    store8(mat[i].as_mut_array_at(BLOCK_IDX), t);
   }
 
   true
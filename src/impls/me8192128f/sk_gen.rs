@@ -23,6 +23,7 @@ fn is_zero_declassify(t: Gf) -> CryptoUint16 {
 /* input: f, element in GF((2^m)^t) */
 /* output: out, minimal polynomial of f */
 /* return: 0 for success and -1 for failure */
+#[allow(clippy::manual_memcpy)]
 pub fn genpoly_gen(out: &mut [Gf; SYS_T], f: &[Gf; SYS_T]) -> bool {
   let mut mat: Box<[[Gf; SYS_T]; SYS_T + 1]> = from_default::<Gf, _, _>();
 
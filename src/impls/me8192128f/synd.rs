@@ -8,15 +8,24 @@ use super::{
   root::eval,
 };
 
-/* input: Goppa polynomial f, support L, received word r */
+/* input: Goppa polynomial f, support L */
+/* output: out = [ 1/f(a)^2 for a in L ] */
+pub fn synd_inv(out: &mut [Gf; SYS_N], f: &[Gf; SYS_T + 1], l: &[Gf; SYS_N]) {
+  for i in 0..SYS_N {
+    let e = eval(f, l[i]);
+    out[i] = e.mul(e).inv();
+  }
+}
+
+/* input: inverses inv computed by synd_inv, support L, received word r */
 /* output: out, the syndrome of length 2t */
-pub fn synd(out: &mut [Gf; SYS_T * 2], f: &[Gf; SYS_T + 1], l: &[Gf; SYS_N], r: &[u8; SYS_N / 8]) {
+#[allow(clippy::needless_range_loop)]
+pub fn synd(out: &mut [Gf; SYS_T * 2], inv: &[Gf; SYS_N], l: &[Gf; SYS_N], r: &[u8; SYS_N / 8]) {
   out.fill(Gf(0));
   for i in 0..SYS_N {
     let c = (r[i / 8] >> (i % 8)) & 1;
     let c = Gf(c as u16);
-    let e = eval(f, l[i]);
-    let mut e_inv = e.mul(e).inv();
+    let mut e_inv = inv[i];
 
     for j in 0..SYS_T * 2 {
       out[j] = out[j].add(e_inv.mul(c));
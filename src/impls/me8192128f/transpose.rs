@@ -2,6 +2,7 @@
   This file is for matrix transposition
 */
 
+#[allow(clippy::identity_op)]
 pub fn transpose_64x64(out: &mut [u64; 64], inp: &[u64; 64]) {
   const MASKS: [[u64; 2]; 6] = [
     [0x5555555555555555, 0xAAAAAAAAAAAAAAAA],
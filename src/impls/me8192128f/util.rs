@@ -29,16 +29,15 @@ pub fn bitrev(a: Gf) -> Gf {
 }
 
 pub trait AsMutArray<T> {
-  fn as_mut_array<const N: usize>(&mut self, offset: usize) -> &mut [T; N];
+  fn as_mut_array_at<const N: usize>(&mut self, offset: usize) -> &mut [T; N];
 }
 
 impl<T> AsMutArray<T> for [T] {
-  fn as_mut_array<const N: usize>(&mut self, offset: usize) -> &mut [T; N] {
+  fn as_mut_array_at<const N: usize>(&mut self, offset: usize) -> &mut [T; N] {
     #[inline]
     unsafe fn as_array<T, const N: usize>(slice: &mut [T]) -> &mut [T; N] {
       &mut *(slice.as_mut_ptr() as *mut [_; N])
     }
-    let offset = offset;
     let slice = &mut self[offset..offset + N];
     #[allow(unused_unsafe)]
     unsafe {
@@ -56,7 +55,6 @@ impl<T> AsRefArray<T> for [T] {
     unsafe fn as_array<T, const N: usize>(slice: &[T]) -> &[T; N] {
       &*(slice.as_ptr() as *const [_; N])
     }
-    let offset = offset;
     let slice = &self[offset..offset + N];
     #[allow(unused_unsafe)]
     unsafe {
@@ -16,6 +16,7 @@ impl<T> CryptoUint<T>
 where
   T: IsSigned,
 {
+  #[allow(clippy::wrong_self_convention)]
   pub fn to_unsigned(self) -> CryptoUint<T::RespectiveUnsigned> {
     CryptoUint(self.0.to_unsigned())
   }
@@ -37,6 +38,7 @@ impl<T> CryptoUint<T>
 where
   T: IsUnsigned,
 {
+  #[allow(clippy::wrong_self_convention)]
   pub fn to_signed(self) -> CryptoUint<T::RespectiveSigned> {
     CryptoUint(self.0.to_signed())
   }
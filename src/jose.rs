@@ -0,0 +1,314 @@
+//! JSON Web Key (RFC 7517) encodings of keys, and JSON Web Encryption
+//! (RFC 7516) with Classic McEliece as the key establishment algorithm.
+//!
+//! Keys are JWKs of the `AKP` (algorithm key pair) type of the JOSE drafts
+//! for post-quantum algorithms: `pub` and `priv` are the base64url encodings
+//! of [`PublicKey::as_bytes`] and [`SecretKey::as_bytes`], and `alg` names the
+//! parameter set (see [`ParameterSet::jose_alg`]). None of these values are
+//! registered with IANA yet.
+//!
+//! # JWE
+//!
+//! [`encrypt`] produces the compact serialization with `alg` set to the name
+//! of the parameter set and `enc` to `A256GCM`. The JWE Encrypted Key is the
+//! [`ShareableSecret`] encapsulated to the recipient's public key, and no key
+//! is wrapped: the content encryption key is derived from the encapsulated
+//! [`SharedKey`] with the Concat KDF of RFC 7518 section 4.6.2 (SHA-256,
+//! `AlgorithmID` `"A256GCM"`, and `apu` and `apv` from the header when
+//! present).
+//!
+//! [`SharedKey`]: crate::SharedKey
+
+use aes_gcm::{
+  aead::{Aead, KeyInit, Payload},
+  Aes256Gcm,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+  Error, ExpandedSecretKey, ParameterSet, PublicKey, SecretKey, ShareableSecret, PARAMETER_SET,
+};
+
+/// `kty` of Classic McEliece keys.
+pub const KTY: &str = "AKP";
+/// `enc` of the JWEs produced by [`encrypt`].
+pub const ENC: &str = "A256GCM";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+impl ParameterSet {
+  pub const fn jose_alg(self) -> &'static str {
+    match self {
+      Self::Mceliece348864 => "MCELIECE348864",
+      Self::Mceliece348864f => "MCELIECE348864F",
+      Self::Mceliece460896 => "MCELIECE460896",
+      Self::Mceliece460896f => "MCELIECE460896F",
+      Self::Mceliece6688128 => "MCELIECE6688128",
+      Self::Mceliece6688128f => "MCELIECE6688128F",
+      Self::Mceliece6960119 => "MCELIECE6960119",
+      Self::Mceliece6960119f => "MCELIECE6960119F",
+      Self::Mceliece8192128 => "MCELIECE8192128",
+      Self::Mceliece8192128f => "MCELIECE8192128F",
+    }
+  }
+  pub fn from_jose_alg(alg: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|set| set.jose_alg() == alg)
+  }
+}
+
+/// A JSON Web Key of type [`KTY`]. `priv` is only present in secret keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct Jwk {
+  pub kty: String,
+  pub alg: String,
+  #[serde(rename = "pub")]
+  pub public: String,
+  #[serde(rename = "priv", default, skip_serializing_if = "Option::is_none")]
+  pub private: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub kid: Option<String>,
+}
+
+impl Jwk {
+  pub fn from_public_key(pk: &PublicKey) -> Self {
+    Self {
+      kty: KTY.to_owned(),
+      alg: PARAMETER_SET.jose_alg().to_owned(),
+      public: BASE64URL.encode(pk.as_bytes()),
+      private: None,
+      kid: None,
+    }
+  }
+  /// Also carries the public key, which is recomputed from `sk`.
+  pub fn from_secret_key(sk: &SecretKey) -> Result<Self, Error> {
+    let mut jwk = Self::from_public_key(&sk.public_key()?);
+    jwk.private = Some(BASE64URL.encode(sk.as_bytes()));
+    Ok(jwk)
+  }
+  fn check_algorithm(&self) -> Result<(), Error> {
+    if self.kty != KTY {
+      return Err(Error::InvalidEncoding);
+    }
+    if self.alg != PARAMETER_SET.jose_alg() {
+      return Err(Error::AlgorithmMismatch);
+    }
+    Ok(())
+  }
+  /// Decodes `pub`. Fails with [`Error::AlgorithmMismatch`] when `alg` names
+  /// another parameter set.
+  pub fn public_key(&self) -> Result<PublicKey, Error> {
+    self.check_algorithm()?;
+    PublicKey::try_from(decode(&self.public)?)
+  }
+  /// Decodes `priv`, which has to be the secret key of `pub`.
+  pub fn secret_key(&self) -> Result<SecretKey, Error> {
+    self.check_algorithm()?;
+    let private = self.private.as_ref().ok_or(Error::InvalidEncoding)?;
+    let sk = SecretKey::try_from(Zeroizing::new(decode(private)?).as_slice())?;
+    if sk.public_key()? != self.public_key()? {
+      return Err(Error::KeyPairMismatch);
+    }
+    Ok(sk)
+  }
+  /// JWK thumbprint (RFC 7638): SHA-256 of the required members `alg`, `kty`
+  /// and `pub`, usable as a `kid`.
+  pub fn thumbprint(&self) -> [u8; 32] {
+    let member = |value: &str| serde_json::to_string(value).unwrap();
+    let canonical = format!(
+      "{{\"alg\":{},\"kty\":{},\"pub\":{}}}",
+      member(&self.alg),
+      member(&self.kty),
+      member(&self.public)
+    );
+    Sha256::digest(canonical.as_bytes()).into()
+  }
+}
+
+fn decode(s: &str) -> Result<Vec<u8>, Error> {
+  BASE64URL.decode(s).map_err(|_| Error::InvalidEncoding)
+}
+
+/// JSON of the JWK of `pk`.
+pub fn encode_public_key(pk: &PublicKey) -> String {
+  serde_json::to_string(&Jwk::from_public_key(pk)).unwrap()
+}
+
+/// JSON of the JWK of `sk`, including its public key.
+pub fn encode_secret_key(sk: &SecretKey) -> Result<Zeroizing<String>, Error> {
+  Ok(Zeroizing::new(
+    serde_json::to_string(&Jwk::from_secret_key(sk)?).unwrap(),
+  ))
+}
+
+pub fn decode_public_key(jwk: &str) -> Result<PublicKey, Error> {
+  serde_json::from_str::<Jwk>(jwk)
+    .map_err(|_| Error::InvalidEncoding)?
+    .public_key()
+}
+
+pub fn decode_secret_key(jwk: &str) -> Result<SecretKey, Error> {
+  serde_json::from_str::<Jwk>(jwk)
+    .map_err(|_| Error::InvalidEncoding)?
+    .secret_key()
+}
+
+/// JOSE protected header of a JWE.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+  alg: String,
+  enc: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  kid: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  apu: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  apv: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  crit: Option<serde_json::Value>,
+}
+
+/* RFC 7518 section 4.6.2; one round of SHA-256 is enough for up to 32 bytes */
+fn concat_kdf(
+  z: &[u8],
+  algorithm_id: &[u8],
+  apu: &[u8],
+  apv: &[u8],
+  len: usize,
+) -> Zeroizing<Vec<u8>> {
+  assert!(len <= 32);
+  let mut hash = Sha256::new();
+  hash.update(1u32.to_be_bytes());
+  hash.update(z);
+  for field in [algorithm_id, apu, apv] {
+    hash.update((field.len() as u32).to_be_bytes());
+    hash.update(field);
+  }
+  hash.update(((len * 8) as u32).to_be_bytes());
+  let mut key = Zeroizing::new(hash.finalize().to_vec());
+  key.truncate(len);
+  key
+}
+
+/// Encrypts `plaintext` to `pk` into a JWE in compact serialization, with
+/// `kid` in the protected header if given.
+#[cfg(feature = "openssl")]
+pub fn encrypt(pk: &PublicKey, plaintext: &[u8], kid: Option<&str>) -> String {
+  encrypt_with_entropy_provider(pk, plaintext, kid, crate::crypto_random)
+}
+
+pub fn encrypt_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  plaintext: &[u8],
+  kid: Option<&str>,
+  mut entropy_provider: F,
+) -> String {
+  let header = Header {
+    alg: PARAMETER_SET.jose_alg().to_owned(),
+    enc: ENC.to_owned(),
+    kid: kid.map(str::to_owned),
+    apu: None,
+    apv: None,
+    crit: None,
+  };
+  let header = BASE64URL.encode(serde_json::to_vec(&header).unwrap());
+  let (enc, key) = pk.encapsulate_with_entropy_provider(&mut entropy_provider);
+  let mut nonce = [0u8; NONCE_LEN];
+  entropy_provider(&mut nonce);
+
+  let content_key = concat_kdf(key.as_bytes(), ENC.as_bytes(), &[], &[], KEY_LEN);
+  let mut ciphertext = Aes256Gcm::new_from_slice(&content_key)
+    .unwrap()
+    .encrypt(
+      (&nonce).into(),
+      Payload {
+        msg: plaintext,
+        aad: header.as_bytes(),
+      },
+    )
+    .unwrap();
+  let tag = ciphertext.split_off(ciphertext.len() - TAG_LEN);
+  [
+    header,
+    BASE64URL.encode(enc.as_bytes()),
+    BASE64URL.encode(nonce),
+    BASE64URL.encode(ciphertext),
+    BASE64URL.encode(tag),
+  ]
+  .join(".")
+}
+
+/// Decrypts a JWE produced by [`encrypt`].
+///
+/// Fails with [`Error::InvalidHeader`] when the JWE is malformed or has a
+/// `crit` header, with [`Error::AlgorithmMismatch`] when `alg` or `enc` are not
+/// the ones of [`encrypt`], and with [`Error::DecryptionFailed`] when it was
+/// encrypted to another key or was modified.
+pub fn decrypt(sk: &SecretKey, jwe: &str) -> Result<Vec<u8>, Error> {
+  decrypt_expanded(&sk.expand(), jwe)
+}
+
+pub fn decrypt_expanded(sk: &ExpandedSecretKey, jwe: &str) -> Result<Vec<u8>, Error> {
+  let parts: Vec<&str> = jwe.split('.').collect();
+  let [header_b64, enc, nonce, ciphertext, tag] = parts[..] else {
+    return Err(Error::InvalidHeader);
+  };
+  let decode = |s: &str| BASE64URL.decode(s).map_err(|_| Error::InvalidHeader);
+  let header: Header =
+    serde_json::from_slice(&decode(header_b64)?).map_err(|_| Error::InvalidHeader)?;
+  if header.crit.is_some() {
+    return Err(Error::InvalidHeader);
+  }
+  if header.alg != PARAMETER_SET.jose_alg() || header.enc != ENC {
+    return Err(Error::AlgorithmMismatch);
+  }
+  let apu = header.apu.as_deref().map(decode).transpose()?;
+  let apv = header.apv.as_deref().map(decode).transpose()?;
+  let enc = ShareableSecret::try_from(&decode(enc)?[..]).map_err(|_| Error::InvalidHeader)?;
+  let nonce = decode(nonce)?;
+  let tag = decode(tag)?;
+  if nonce.len() != NONCE_LEN || tag.len() != TAG_LEN {
+    return Err(Error::InvalidHeader);
+  }
+  let mut msg = decode(ciphertext)?;
+  msg.extend_from_slice(&tag);
+
+  let key = enc.try_decapsulate_expanded(sk)?;
+  let content_key = concat_kdf(
+    key.as_bytes(),
+    ENC.as_bytes(),
+    apu.as_deref().unwrap_or_default(),
+    apv.as_deref().unwrap_or_default(),
+    KEY_LEN,
+  );
+  Aes256Gcm::new_from_slice(&content_key)
+    .unwrap()
+    .decrypt(
+      nonce[..].into(),
+      Payload {
+        msg: &msg,
+        aad: header_b64.as_bytes(),
+      },
+    )
+    .map_err(|_| Error::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /* RFC 7518 appendix C, ECDH-ES key agreement for A128GCM */
+  #[test]
+  fn rfc7518_concat_kdf() {
+    let z = [
+      158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49, 110, 163,
+      218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+    ];
+    let key = concat_kdf(&z, b"A128GCM", b"Alice", b"Bob", 16);
+    assert_eq!(BASE64URL.encode(&key[..]), "VqqN6vgjbSBcIijNcacQGg");
+  }
+}
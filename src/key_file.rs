@@ -0,0 +1,174 @@
+//! Self-describing key files, which name the parameter set and the kind of the
+//! key they hold, so that keys are not mistaken for those of another parameter
+//! set or build.
+//!
+//! # Format (version 1)
+//!
+//! ```text
+//! key file = "RMCK" || version (0x01) || parameter set (u16, big-endian)
+//!            || key type (0x01 public, 0x02 secret, 0x03 encrypted secret)
+//!            || key ID length (u8) || key ID
+//!            || key length (u32, big-endian) || key || checksum
+//! ```
+//!
+//! The parameter set is [`ParameterSet::id`], the key is the bytes of
+//! [`PublicKey::as_bytes`] or [`SecretKey::as_bytes`], or a secret key
+//! encrypted under a passphrase as described in `rmce::passphrase`, and the
+//! checksum is the first 4 bytes of SHAKE256 of everything before it. An empty
+//! key ID stands for no key ID. The checksum only catches corruption: key
+//! files are not authenticated, apart from what the encryption of an encrypted
+//! secret key covers.
+
+use zeroize::Zeroizing;
+
+use crate::{impls::libkeccak::shake256, Error, ParameterSet, PublicKey, SecretKey, PARAMETER_SET};
+
+const MAGIC: &[u8; 4] = b"RMCK";
+const VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+const MIN_LEN: usize = MAGIC.len() + 1 + 2 + 1 + 1 + 4 + CHECKSUM_LEN;
+
+/// Longest key ID of a key file.
+pub const MAX_KEY_ID_LEN: usize = u8::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+  Public = 1,
+  Secret = 2,
+  EncryptedSecret = 3,
+}
+
+/// Fields of a key file, borrowed from its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyFile<'a> {
+  pub parameter_set: ParameterSet,
+  pub key_type: KeyType,
+  pub key_id: Option<&'a [u8]>,
+  pub key: &'a [u8],
+}
+
+impl<'a> KeyFile<'a> {
+  /// Checks the header and the checksum, but neither the parameter set nor
+  /// the length of the key. Fails with [`Error::InvalidHeader`] when the magic,
+  /// version or key type is unknown or the lengths do not add up, with
+  /// [`Error::AlgorithmMismatch`] when the parameter set is unknown and with
+  /// [`Error::InvalidEncoding`] when the checksum is wrong.
+  pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+    if bytes.len() < MIN_LEN {
+      return Err(Error::InvalidLength {
+        got: bytes.len(),
+        expected: MIN_LEN,
+      });
+    }
+    if &bytes[..MAGIC.len()] != MAGIC || bytes[MAGIC.len()] != VERSION {
+      return Err(Error::InvalidHeader);
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    let rest = &body[MAGIC.len() + 1..];
+    let parameter_set = ParameterSet::from_id(u16::from_be_bytes([rest[0], rest[1]]))
+      .ok_or(Error::AlgorithmMismatch)?;
+    let key_type = match rest[2] {
+      1 => KeyType::Public,
+      2 => KeyType::Secret,
+      3 => KeyType::EncryptedSecret,
+      _ => return Err(Error::InvalidHeader),
+    };
+    let key_id_len = rest[3] as usize;
+    let rest = &rest[4..];
+    if rest.len() < key_id_len + 4 {
+      return Err(Error::InvalidHeader);
+    }
+    let (key_id, rest) = rest.split_at(key_id_len);
+    let (key_len, key) = rest.split_at(4);
+    if u32::from_be_bytes(key_len.try_into().unwrap()) as usize != key.len() {
+      return Err(Error::InvalidHeader);
+    }
+    if checksum != compute_checksum(body) {
+      return Err(Error::InvalidEncoding);
+    }
+    Ok(Self {
+      parameter_set,
+      key_type,
+      key_id: (!key_id.is_empty()).then_some(key_id),
+      key,
+    })
+  }
+
+  /// Fails with [`Error::InvalidKeyIdLength`] when the key ID is longer than
+  /// [`MAX_KEY_ID_LEN`].
+  pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+    let key_id = self.key_id.unwrap_or_default();
+    if key_id.len() > MAX_KEY_ID_LEN {
+      return Err(Error::InvalidKeyIdLength {
+        got: key_id.len(),
+        max: MAX_KEY_ID_LEN,
+      });
+    }
+    let mut bytes = Vec::with_capacity(MIN_LEN + key_id.len() + self.key.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&self.parameter_set.id().to_be_bytes());
+    bytes.push(self.key_type as u8);
+    bytes.push(key_id.len() as u8);
+    bytes.extend_from_slice(key_id);
+    bytes.extend_from_slice(&(self.key.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(self.key);
+    let checksum = compute_checksum(&bytes);
+    bytes.extend_from_slice(&checksum);
+    Ok(bytes)
+  }
+
+  /* the key of PARAMETER_SET and `key_type` */
+  pub(crate) fn key(bytes: &'a [u8], key_type: KeyType) -> Result<&'a [u8], Error> {
+    let file = Self::parse(bytes)?;
+    if file.parameter_set != PARAMETER_SET {
+      return Err(Error::AlgorithmMismatch);
+    }
+    if file.key_type != key_type {
+      return Err(Error::InvalidHeader);
+    }
+    Ok(file.key)
+  }
+}
+
+fn compute_checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+  let mut checksum = [0u8; CHECKSUM_LEN];
+  shake256(&mut checksum, bytes);
+  checksum
+}
+
+impl PublicKey {
+  /// Key file of the key, see [`crate::key_file`].
+  pub fn to_file_bytes(&self, key_id: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+    KeyFile {
+      parameter_set: PARAMETER_SET,
+      key_type: KeyType::Public,
+      key_id,
+      key: self.as_bytes(),
+    }
+    .to_bytes()
+  }
+  /// Reads the key of a public key file of [`PARAMETER_SET`].
+  /// The key ID is available from [`KeyFile::parse`].
+  pub fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    Self::try_from(KeyFile::key(bytes, KeyType::Public)?)
+  }
+}
+
+impl SecretKey {
+  pub fn to_file_bytes(&self, key_id: Option<&[u8]>) -> Result<Zeroizing<Vec<u8>>, Error> {
+    KeyFile {
+      parameter_set: PARAMETER_SET,
+      key_type: KeyType::Secret,
+      key_id,
+      key: self.as_bytes(),
+    }
+    .to_bytes()
+    .map(Zeroizing::new)
+  }
+  pub fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    let sk = Self::try_from(KeyFile::key(bytes, KeyType::Secret)?)?;
+    sk.validate()?;
+    Ok(sk)
+  }
+}
@@ -1,9 +1,46 @@
+#[cfg(feature = "age")]
+pub mod age;
+#[cfg(feature = "asn1")]
+pub mod asn1;
+#[cfg(feature = "cms")]
+pub mod cms;
+#[cfg(any(feature = "hybrid", feature = "ml-kem"))]
+mod combiner;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(feature = "serde")]
+pub mod encoding;
+#[cfg(feature = "envelope")]
+pub mod envelope;
+#[cfg(feature = "hash")]
+pub mod hash;
+#[cfg(feature = "hpke")]
+pub mod hpke;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+#[cfg(feature = "ml-kem")]
+pub mod hybrid_ml_kem;
 mod impls;
+#[cfg(feature = "jose")]
+pub mod jose;
+pub mod key_file;
+mod parameter_set;
+#[cfg(feature = "passphrase")]
+pub mod passphrase;
+#[cfg(feature = "seal")]
+pub mod seal;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "x509")]
+pub mod x509;
 
 use boxed_array::from_default;
-use serde::{de::Visitor, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
-use impls::me8192128f::{CIPHER_TEXT_LEN, PUBLIC_KEY_LEN, SECRET_KEY_LEN};
+use impls::me8192128f::{CIPHER_TEXT_LEN, PUBLIC_KEY_LEN, SECRET_KEY_LEN, SHARED_KEY_LEN};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub use parameter_set::{ParameterSet, PARAMETER_SET};
 
 #[cfg(feature = "openssl")]
 fn crypto_random(data: &mut [u8]) {
@@ -18,48 +55,7 @@ impl Serialize for PublicKey {
   where
     S: serde::Serializer,
   {
-    serializer.serialize_bytes(self.as_bytes())
-  }
-}
-
-pub struct BoxedArrayVisitor<const SIZE: usize>;
-pub struct ArrayVisitor<const SIZE: usize>;
-impl<'de, const SIZE: usize> Visitor<'de> for BoxedArrayVisitor<SIZE> {
-  type Value = Box<[u8; SIZE]>;
-  fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-    formatter.write_fmt(format_args!("raw bytes of length: {}", SIZE))
-  }
-  fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
-  where
-    E: serde::de::Error,
-  {
-    let len = v.len();
-    v.into_boxed_slice()
-      .try_into()
-      .map_err(|_| E::invalid_length(len, &self))
-  }
-  fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-  where
-    E: serde::de::Error,
-  {
-    if v.len() != SIZE {
-      return Err(E::invalid_length(v.len(), &self));
-    }
-    let mut arr: Box<[u8; SIZE]> = boxed_array::from_default();
-    arr.copy_from_slice(v);
-    Ok(arr)
-  }
-}
-impl<'de, const SIZE: usize> Visitor<'de> for ArrayVisitor<SIZE> {
-  type Value = [u8; SIZE];
-  fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-    formatter.write_fmt(format_args!("raw bytes of length: {}", SIZE))
-  }
-  fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-  where
-    E: serde::de::Error,
-  {
-    v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+    encoding::base64::serialize(self, serializer)
   }
 }
 
@@ -68,8 +64,7 @@ impl<'de> Deserialize<'de> for PublicKey {
   where
     D: serde::Deserializer<'de>,
   {
-    let contents = deserializer.deserialize_byte_buf(BoxedArrayVisitor)?;
-    Ok(Self(contents))
+    encoding::base64::deserialize(deserializer)
   }
 }
 
@@ -84,10 +79,36 @@ impl PublicKey {
   pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
     &self.0
   }
+  pub fn from_seed(seed: [u8; SecretKey::SEED_SIZE]) -> Self {
+    generate_keypair_from_seed(seed).0
+  }
+  #[cfg(feature = "openssl")]
+  pub fn encapsulate(&self) -> (ShareableSecret, SharedKey) {
+    self.encapsulate_with_entropy_provider(crypto_random)
+  }
+  pub fn encapsulate_with_entropy_provider<F: FnMut(&mut [u8])>(
+    &self,
+    entropy_provider: F,
+  ) -> (ShareableSecret, SharedKey) {
+    let mut shared = ShareableSecret([0u8; ShareableSecret::SIZE]);
+    let mut key = SharedKey([0u8; SharedKey::SIZE]);
+    impls::me8192128f::operations::crypto_kem_enc(
+      &mut shared.0,
+      &mut key.0,
+      &self.0,
+      entropy_provider,
+    );
+    (shared, key)
+  }
   #[cfg(feature = "openssl")]
   pub fn session(&self, plain_secret_len: usize) -> (ShareableSecret, PlainSecret) {
     self.session_with_entropy_provider(plain_secret_len, crypto_random)
   }
+  /// Squeezes `plain_secret_len` bytes out of the same hash that produces the
+  /// [`SharedKey`]. The length is not bound into the hash, so outputs of
+  /// different lengths are prefixes of each other; use
+  /// [`PublicKey::encapsulate_with_entropy_provider`] and [`SharedKey::kdf`] for
+  /// length-bound keys.
   pub fn session_with_entropy_provider<F: FnMut(&mut [u8])>(
     &self,
     plain_secret_len: usize,
@@ -114,6 +135,21 @@ impl PublicKey {
 #[derive(Debug, Clone, Copy)]
 pub enum Error {
   InvalidLength { got: usize, expected: usize },
+  InvalidPublicKey,
+  InvalidSecretKey,
+  InvalidCiphertext,
+  KeyPairMismatch,
+  OutputTooLong { got: usize, max: usize },
+  InvalidPsk,
+  DecryptionFailed,
+  MessageLimitReached,
+  InvalidHeader,
+  InvalidRecipientCount { got: usize, max: usize },
+  InvalidEncoding,
+  AlgorithmMismatch,
+  SigningFailed,
+  InvalidKeyIdLength { got: usize, max: usize },
+  InvalidKdfParams,
 }
 
 impl std::error::Error for Error {}
@@ -161,7 +197,7 @@ impl Serialize for SecretKey {
   where
     S: serde::Serializer,
   {
-    serializer.serialize_bytes(self.as_bytes())
+    encoding::base64::serialize(self, serializer)
   }
 }
 
@@ -170,8 +206,7 @@ impl<'de> Deserialize<'de> for SecretKey {
   where
     D: serde::Deserializer<'de>,
   {
-    let contents = deserializer.deserialize_byte_buf(BoxedArrayVisitor)?;
-    Ok(Self(contents))
+    encoding::base64::deserialize(deserializer)
   }
 }
 
@@ -183,9 +218,58 @@ impl From<Box<[u8; Self::SIZE]>> for SecretKey {
 
 impl SecretKey {
   pub const SIZE: usize = SECRET_KEY_LEN;
+  pub const SEED_SIZE: usize = 32;
   pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
     &self.0
   }
+  /// Regenerates the secret key from the 32-byte keygen seed (delta).
+  pub fn from_seed(seed: [u8; Self::SEED_SIZE]) -> Self {
+    generate_keypair_from_seed(seed).1
+  }
+  /// Returns the keygen seed stored in the first bytes of the secret key.
+  /// Passing it to [`SecretKey::from_seed`] or [`PublicKey::from_seed`]
+  /// reproduces this key pair.
+  pub fn seed(&self) -> [u8; Self::SEED_SIZE] {
+    self.0[..Self::SEED_SIZE].try_into().unwrap()
+  }
+  /// Recomputes the public key belonging to this secret key.
+  pub fn public_key(&self) -> Result<PublicKey, Error> {
+    let mut pk = PublicKey(from_default());
+    if !impls::me8192128f::operations::crypto_kem_pk_from_sk(&mut pk.0, &self.0) {
+      return Err(Error::InvalidSecretKey);
+    }
+    Ok(pk)
+  }
+  /// Checks the encoding of the key on its own: see [`validate_pair`] for what
+  /// is and is not checked. Takes noticeably longer than decapsulation and is
+  /// not constant-time.
+  pub fn validate(&self) -> Result<(), Error> {
+    if !impls::me8192128f::operations::crypto_kem_sk_check(&self.0) {
+      return Err(Error::InvalidSecretKey);
+    }
+    Ok(())
+  }
+  pub fn expand(&self) -> ExpandedSecretKey {
+    ExpandedSecretKey(impls::me8192128f::operations::crypto_kem_sk_expand(&self.0))
+  }
+}
+
+/// Secret key with the support, the Goppa polynomial and the inverses used by
+/// syndrome computation precomputed, for repeated decapsulation with the same key.
+/// Contents are zeroized on drop.
+#[derive(Clone)]
+pub struct ExpandedSecretKey(impls::me8192128f::operations::ExpandedSecretKey);
+
+impl std::fmt::Debug for ExpandedSecretKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ExpandedSecretKey").finish_non_exhaustive()
+  }
+}
+
+impl From<&SecretKey> for ExpandedSecretKey {
+  fn from(sk: &SecretKey) -> Self {
+    sk.expand()
+  }
 }
 
 impl TryFrom<&[u8]> for SecretKey {
@@ -231,6 +315,38 @@ pub fn generate_keypair_with_entropy_provider<F: FnMut(&mut [u8])>(
   (pk, sk)
 }
 
+pub fn generate_keypair_from_seed(seed: [u8; SecretKey::SEED_SIZE]) -> (PublicKey, SecretKey) {
+  generate_keypair_with_entropy_provider(|data| data.copy_from_slice(&seed))
+}
+
+/// Checks that `sk` is well-formed and that it produces `pk`. This is the
+/// only check of a public key: every bit string of the right length is the
+/// encoding of some mceliece8192128f public key.
+///
+/// Checked, failing with [`Error::InvalidSecretKey`]:
+/// - the Goppa polynomial has coefficients in the field and is irreducible;
+/// - the control bits are the canonical encoding of their permutation;
+/// - the pivots field has 32 bits set.
+///
+/// Then the public key is recomputed from the Goppa polynomial and the
+/// permutation, failing with [`Error::KeyPairMismatch`] when it is not `pk`.
+/// This catches corrupted control bits that still encode a permutation.
+///
+/// Not checked: which 32 columns the pivots field names. Keygen records the
+/// columns it swapped, but the permutation before the swap is not kept, so
+/// the field cannot be recomputed. Decapsulation does not read it. The seed
+/// and the implicit-rejection string `s` are random and cannot be checked
+/// either.
+///
+/// Takes as long as key generation and is not constant-time.
+pub fn validate_pair(pk: &PublicKey, sk: &SecretKey) -> Result<(), Error> {
+  sk.validate()?;
+  if &sk.public_key()? != pk {
+    return Err(Error::KeyPairMismatch);
+  }
+  Ok(())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ShareableSecret([u8; Self::SIZE]);
 
@@ -241,12 +357,23 @@ impl TryFrom<Vec<u8>> for ShareableSecret {
   }
 }
 
+impl TryFrom<&[u8]> for ShareableSecret {
+  type Error = Error;
+  fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+    let ct: [u8; Self::SIZE] = value.try_into().map_err(|_| Error::InvalidLength {
+      got: value.len(),
+      expected: Self::SIZE,
+    })?;
+    Ok(Self(ct))
+  }
+}
+
 impl Serialize for ShareableSecret {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
     S: serde::Serializer,
   {
-    serializer.serialize_bytes(&self.0)
+    encoding::base64::serialize(self, serializer)
   }
 }
 
@@ -255,18 +382,51 @@ impl<'de> Deserialize<'de> for ShareableSecret {
   where
     D: serde::Deserializer<'de>,
   {
-    let contents = deserializer.deserialize_bytes(ArrayVisitor)?;
-    Ok(Self(contents))
+    encoding::base64::deserialize(deserializer)
   }
 }
 
 impl ShareableSecret {
   pub const SIZE: usize = CIPHER_TEXT_LEN;
+  pub fn decapsulate(&self, sk: &SecretKey) -> SharedKey {
+    let mut key = SharedKey([0u8; SharedKey::SIZE]);
+    impls::me8192128f::operations::crypto_kem_dec(&mut key.0, &self.0, &sk.0);
+    key
+  }
+  pub fn decapsulate_expanded(&self, sk: &ExpandedSecretKey) -> SharedKey {
+    let mut key = SharedKey([0u8; SharedKey::SIZE]);
+    impls::me8192128f::operations::crypto_kem_dec_expanded(&mut key.0, &self.0, &sk.0);
+    key
+  }
+  /// Runs [`ShareableSecret::validate`] before decapsulating. For mceliece8192128f
+  /// every ciphertext is well-formed, so this never fails and is the same as
+  /// [`ShareableSecret::decapsulate`]: a ciphertext that fails to decode yields a
+  /// pseudorandom [`SharedKey`] (implicit rejection).
+  pub fn try_decapsulate(&self, sk: &SecretKey) -> Result<SharedKey, Error> {
+    self.validate()?;
+    Ok(self.decapsulate(sk))
+  }
+  pub fn try_decapsulate_expanded(&self, sk: &ExpandedSecretKey) -> Result<SharedKey, Error> {
+    self.validate()?;
+    Ok(self.decapsulate_expanded(sk))
+  }
+  /// Counterpart of [`PublicKey::session`]: `plain_secret_len` must match the
+  /// length used there.
   pub fn open(&self, plain_secret_len: usize, sk: &SecretKey) -> PlainSecret {
     let mut plain = PlainSecret(vec![0u8; plain_secret_len]);
     impls::me8192128f::operations::crypto_kem_dec(&mut plain.0, &self.0, &sk.0);
     plain
   }
+  /// Checks that the padding bits of the syndrome are zero. A mceliece8192128f
+  /// syndrome is 1664 bits, which fill whole bytes, so there is nothing to check
+  /// and this always returns `Ok`; [`Error::InvalidCiphertext`] is only reachable
+  /// for parameter sets with padding.
+  pub fn validate(&self) -> Result<(), Error> {
+    if !impls::me8192128f::operations::crypto_kem_ct_check(&self.0) {
+      return Err(Error::InvalidCiphertext);
+    }
+    Ok(())
+  }
   pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
     &self.0
   }
@@ -284,13 +444,144 @@ impl From<[u8; ShareableSecret::SIZE]> for ShareableSecret {
   }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Session key as defined by the specification: 32 bytes of SHAKE256 output
+/// over the error vector and the ciphertext.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SharedKey([u8; Self::SIZE]);
+
+impl std::fmt::Debug for SharedKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SharedKey").finish_non_exhaustive()
+  }
+}
+
+impl Serialize for SharedKey {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    encoding::base64::serialize(self, serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for SharedKey {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    encoding::base64::deserialize(deserializer)
+  }
+}
+
+impl SharedKey {
+  pub const SIZE: usize = SHARED_KEY_LEN;
+  pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
+    &self.0
+  }
+  /// Derives `len` bytes from the key with SHAKE256. The length is hashed
+  /// together with the key, so outputs of different lengths are unrelated.
+  pub fn kdf(&self, len: usize) -> PlainSecret {
+    let mut preimage = Vec::with_capacity(KDF_DOMAIN.len() + 8 + Self::SIZE);
+    preimage.extend_from_slice(KDF_DOMAIN);
+    preimage.extend_from_slice(&(len as u64).to_le_bytes());
+    preimage.extend_from_slice(&self.0);
+    let mut plain = PlainSecret(vec![0u8; len]);
+    impls::libkeccak::shake256(&mut plain.0, &preimage);
+    preimage.zeroize();
+    plain
+  }
+  /// See [`PlainSecret::derive`].
+  pub fn derive(&self, label: &[u8], context: &[u8], len: usize) -> PlainSecret {
+    kmac_derive(&self.0, label, context, len)
+  }
+  /// See [`PlainSecret::derive_hkdf`].
+  #[cfg(feature = "hkdf")]
+  pub fn derive_hkdf(
+    &self,
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+  ) -> Result<PlainSecret, Error> {
+    hkdf_derive(&self.0, label, context, len)
+  }
+}
+
+const KDF_DOMAIN: &[u8] = b"rmce-kdf-v1";
+
+impl From<[u8; SharedKey::SIZE]> for SharedKey {
+  fn from(value: [u8; SharedKey::SIZE]) -> Self {
+    Self(value)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PlainSecret(Vec<u8>);
 
+impl Serialize for PlainSecret {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    encoding::base64::serialize(self, serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for PlainSecret {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    encoding::base64::deserialize(deserializer)
+  }
+}
+
 impl PlainSecret {
   pub fn as_bytes(&self) -> &[u8] {
     &self.0
   }
+  /// Derives a `len`-byte key with KMAC256 keyed by this secret, using `label`
+  /// as the customization string and `context` as the message. Different labels
+  /// give independent keys, so several keys can be taken from one encapsulation.
+  pub fn derive(&self, label: &[u8], context: &[u8], len: usize) -> PlainSecret {
+    kmac_derive(&self.0, label, context, len)
+  }
+  /// Same as [`PlainSecret::derive`], but with HKDF-SHA256 (RFC 5869). The secret
+  /// is the input keying material, the salt is empty and the info is the length of
+  /// `label` as a big-endian u64, followed by `label` and `context`.
+  #[cfg(feature = "hkdf")]
+  pub fn derive_hkdf(
+    &self,
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+  ) -> Result<PlainSecret, Error> {
+    hkdf_derive(&self.0, label, context, len)
+  }
+}
+
+fn kmac_derive(secret: &[u8], label: &[u8], context: &[u8], len: usize) -> PlainSecret {
+  let mut plain = PlainSecret(vec![0u8; len]);
+  impls::libkeccak::kmac256(&mut plain.0, secret, context, label);
+  plain
+}
+
+#[cfg(feature = "hkdf")]
+fn hkdf_derive(
+  secret: &[u8],
+  label: &[u8],
+  context: &[u8],
+  len: usize,
+) -> Result<PlainSecret, Error> {
+  const MAX: usize = 255 * 32;
+  let mut info = Vec::with_capacity(8 + label.len() + context.len());
+  info.extend_from_slice(&(label.len() as u64).to_be_bytes());
+  info.extend_from_slice(label);
+  info.extend_from_slice(context);
+  let mut plain = PlainSecret(vec![0u8; len]);
+  hkdf::Hkdf::<sha2::Sha256>::new(None, secret)
+    .expand(&info, &mut plain.0)
+    .map_err(|_| Error::OutputTooLong { got: len, max: MAX })?;
+  Ok(plain)
 }
 
 impl From<PlainSecret> for Vec<u8> {
@@ -301,6 +592,6 @@ impl From<PlainSecret> for Vec<u8> {
 
 impl From<Vec<u8>> for PlainSecret {
   fn from(value: Vec<u8>) -> Self {
-    Self(value.into())
+    Self(value)
   }
 }
@@ -0,0 +1,65 @@
+/// Classic McEliece parameter sets, for the identifiers of the key and message
+/// encodings. Only [`PARAMETER_SET`] is implemented by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParameterSet {
+  Mceliece348864,
+  Mceliece348864f,
+  Mceliece460896,
+  Mceliece460896f,
+  Mceliece6688128,
+  Mceliece6688128f,
+  Mceliece6960119,
+  Mceliece6960119f,
+  Mceliece8192128,
+  Mceliece8192128f,
+}
+
+impl ParameterSet {
+  pub const ALL: [Self; 10] = [
+    Self::Mceliece348864,
+    Self::Mceliece348864f,
+    Self::Mceliece460896,
+    Self::Mceliece460896f,
+    Self::Mceliece6688128,
+    Self::Mceliece6688128f,
+    Self::Mceliece6960119,
+    Self::Mceliece6960119f,
+    Self::Mceliece8192128,
+    Self::Mceliece8192128f,
+  ];
+  pub const fn name(self) -> &'static str {
+    match self {
+      Self::Mceliece348864 => "mceliece348864",
+      Self::Mceliece348864f => "mceliece348864f",
+      Self::Mceliece460896 => "mceliece460896",
+      Self::Mceliece460896f => "mceliece460896f",
+      Self::Mceliece6688128 => "mceliece6688128",
+      Self::Mceliece6688128f => "mceliece6688128f",
+      Self::Mceliece6960119 => "mceliece6960119",
+      Self::Mceliece6960119f => "mceliece6960119f",
+      Self::Mceliece8192128 => "mceliece8192128",
+      Self::Mceliece8192128f => "mceliece8192128f",
+    }
+  }
+  /// Number of the parameter set in [`crate::key_file`] containers.
+  pub const fn id(self) -> u16 {
+    match self {
+      Self::Mceliece348864 => 1,
+      Self::Mceliece348864f => 2,
+      Self::Mceliece460896 => 3,
+      Self::Mceliece460896f => 4,
+      Self::Mceliece6688128 => 5,
+      Self::Mceliece6688128f => 6,
+      Self::Mceliece6960119 => 7,
+      Self::Mceliece6960119f => 8,
+      Self::Mceliece8192128 => 9,
+      Self::Mceliece8192128f => 10,
+    }
+  }
+  pub fn from_id(id: u16) -> Option<Self> {
+    Self::ALL.into_iter().find(|set| set.id() == id)
+  }
+}
+
+/// Parameter set implemented by this crate.
+pub const PARAMETER_SET: ParameterSet = ParameterSet::Mceliece8192128f;
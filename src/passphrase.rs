@@ -0,0 +1,204 @@
+//! Secret keys encrypted under a passphrase, stored as key files of type
+//! [`KeyType::EncryptedSecret`] (see [`crate::key_file`]).
+//!
+//! # Format (version 1)
+//!
+//! ```text
+//! key = memory (u32, big-endian, KiB) || iterations (u32, big-endian)
+//!       || parallelism (u32, big-endian) || salt (16 bytes) || nonce (24 bytes)
+//!       || sealed secret key
+//! ```
+//!
+//! The secret key is sealed with XChaCha20-Poly1305 under Argon2id (version
+//! 0x13) of the passphrase and the salt with the given costs. The parameter
+//! set of the key file and everything of the key before the sealed secret key
+//! are authenticated along with it; the key ID is not.
+//!
+//! The costs are read from the key file. Before deriving the key, decryption
+//! refuses costs above [`KdfParams::MAX`], or above the limits given to
+//! [`SecretKey::decrypt_with_passphrase_and_limits`], so a key file from an
+//! untrusted source cannot ask for arbitrary amounts of memory and time.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+  aead::{Aead, KeyInit, Payload},
+  XChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  key_file::{KeyFile, KeyType},
+  Error, SecretKey, PARAMETER_SET,
+};
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Length of the key of an encrypted key file.
+pub const ENCRYPTED_LEN: usize = HEADER_LEN + SecretKey::SIZE + TAG_LEN;
+
+/// Argon2id costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+  pub memory_kib: u32,
+  pub iterations: u32,
+  pub parallelism: u32,
+}
+
+/// The second recommended option of RFC 9106: 64 MiB, 3 iterations and 4
+/// lanes.
+impl Default for KdfParams {
+  fn default() -> Self {
+    Self {
+      memory_kib: 64 * 1024,
+      iterations: 3,
+      parallelism: 4,
+    }
+  }
+}
+
+impl KdfParams {
+  /// Highest costs [`SecretKey::decrypt_with_passphrase`] accepts: 2 GiB (the
+  /// first recommended option of RFC 9106), 8 iterations and 16 lanes.
+  pub const MAX: Self = Self {
+    memory_kib: 2 * 1024 * 1024,
+    iterations: 8,
+    parallelism: 16,
+  };
+  fn within(&self, limits: &Self) -> bool {
+    self.memory_kib <= limits.memory_kib
+      && self.iterations <= limits.iterations
+      && self.parallelism <= limits.parallelism
+  }
+  fn derive_key(&self, passphrase: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; KEY_LEN]>, Error> {
+    let params = Params::new(
+      self.memory_kib,
+      self.iterations,
+      self.parallelism,
+      Some(KEY_LEN),
+    )
+    .map_err(|_| Error::InvalidKdfParams)?;
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+      .hash_password_into(passphrase, salt, key.as_mut())
+      .map_err(|_| Error::InvalidKdfParams)?;
+    Ok(key)
+  }
+}
+
+/* the parameter set, which is outside of the key, is authenticated too */
+fn aad(header: &[u8]) -> Vec<u8> {
+  [&PARAMETER_SET.id().to_be_bytes()[..], header].concat()
+}
+
+impl SecretKey {
+  /// Key file of the key encrypted under `passphrase`. Fails with
+  /// [`Error::InvalidKdfParams`] when Argon2 does not accept `params` and
+  /// with [`Error::InvalidKeyIdLength`] when the key ID is too long. Costs
+  /// above [`KdfParams::MAX`] give a key file that only
+  /// [`SecretKey::decrypt_with_passphrase_and_limits`] decrypts.
+  #[cfg(feature = "openssl")]
+  pub fn encrypt_with_passphrase(
+    &self,
+    passphrase: &[u8],
+    params: KdfParams,
+    key_id: Option<&[u8]>,
+  ) -> Result<Vec<u8>, Error> {
+    self.encrypt_with_passphrase_with_entropy_provider(
+      passphrase,
+      params,
+      key_id,
+      crate::crypto_random,
+    )
+  }
+  pub fn encrypt_with_passphrase_with_entropy_provider<F: FnMut(&mut [u8])>(
+    &self,
+    passphrase: &[u8],
+    params: KdfParams,
+    key_id: Option<&[u8]>,
+    mut entropy_provider: F,
+  ) -> Result<Vec<u8>, Error> {
+    let mut encrypted = Vec::with_capacity(ENCRYPTED_LEN);
+    encrypted.extend_from_slice(&params.memory_kib.to_be_bytes());
+    encrypted.extend_from_slice(&params.iterations.to_be_bytes());
+    encrypted.extend_from_slice(&params.parallelism.to_be_bytes());
+    let mut salt_and_nonce = [0u8; SALT_LEN + NONCE_LEN];
+    entropy_provider(&mut salt_and_nonce);
+    encrypted.extend_from_slice(&salt_and_nonce);
+    let (salt, nonce) = salt_and_nonce.split_at(SALT_LEN);
+
+    let key = params.derive_key(passphrase, salt)?;
+    let sealed = XChaCha20Poly1305::new((&*key).into())
+      .encrypt(
+        nonce.into(),
+        Payload {
+          msg: self.as_bytes(),
+          aad: &aad(&encrypted),
+        },
+      )
+      .unwrap();
+    encrypted.extend_from_slice(&sealed);
+    KeyFile {
+      parameter_set: PARAMETER_SET,
+      key_type: KeyType::EncryptedSecret,
+      key_id,
+      key: &encrypted,
+    }
+    .to_bytes()
+  }
+
+  /// Decrypts a key file written by [`SecretKey::encrypt_with_passphrase`].
+  /// Fails like [`KeyFile::parse`], with [`Error::InvalidHeader`] when the
+  /// key file does not hold an encrypted secret key or Argon2 does not accept
+  /// its costs, with [`Error::InvalidKdfParams`] when its costs exceed
+  /// [`KdfParams::MAX`], with [`Error::AlgorithmMismatch`] when the key is of
+  /// another parameter set, and with [`Error::DecryptionFailed`] when the
+  /// passphrase is wrong or the key was altered.
+  pub fn decrypt_with_passphrase(bytes: &[u8], passphrase: &[u8]) -> Result<Self, Error> {
+    Self::decrypt_with_passphrase_and_limits(bytes, passphrase, KdfParams::MAX)
+  }
+  /// Like [`SecretKey::decrypt_with_passphrase`], but fails with
+  /// [`Error::InvalidKdfParams`] when the costs exceed `limits` instead.
+  pub fn decrypt_with_passphrase_and_limits(
+    bytes: &[u8],
+    passphrase: &[u8],
+    limits: KdfParams,
+  ) -> Result<Self, Error> {
+    let encrypted = KeyFile::key(bytes, KeyType::EncryptedSecret)?;
+    if encrypted.len() != ENCRYPTED_LEN {
+      return Err(Error::InvalidLength {
+        got: encrypted.len(),
+        expected: ENCRYPTED_LEN,
+      });
+    }
+    let (header, sealed) = encrypted.split_at(HEADER_LEN);
+    let u32_at = |i: usize| u32::from_be_bytes(header[i..i + 4].try_into().unwrap());
+    let params = KdfParams {
+      memory_kib: u32_at(0),
+      iterations: u32_at(4),
+      parallelism: u32_at(8),
+    };
+    if !params.within(&limits) {
+      return Err(Error::InvalidKdfParams);
+    }
+    let (salt, nonce) = header[12..].split_at(SALT_LEN);
+
+    let key = params
+      .derive_key(passphrase, salt)
+      .map_err(|_| Error::InvalidHeader)?;
+    let plain = XChaCha20Poly1305::new((&*key).into())
+      .decrypt(
+        nonce.into(),
+        Payload {
+          msg: sealed,
+          aad: &aad(header),
+        },
+      )
+      .map_err(|_| Error::DecryptionFailed)?;
+    let plain = Zeroizing::new(plain);
+    Self::try_from(&plain[..])
+  }
+}
@@ -0,0 +1,118 @@
+//! Encryption of a single message to a public key.
+//!
+//! # Format (version 1)
+//!
+//! ```text
+//! blob = "RMCE" || version (0x01) || ShareableSecret || ciphertext || tag
+//! ```
+//!
+//! The ChaCha20-Poly1305 key and nonce are SHAKE256 of `"rmce-seal-v1"`, the
+//! [`SharedKey`] and the header, and the header is authenticated together with
+//! the caller's associated data. For inputs too large to hold in memory use
+//! [`crate::stream`].
+
+use chacha20poly1305::{
+  aead::{Aead, KeyInit, Payload},
+  ChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  impls::libkeccak::{Sponge, SHAKE256_RATE, SHAKE_SUFFIX},
+  Error, ExpandedSecretKey, PublicKey, SecretKey, ShareableSecret, SharedKey,
+};
+
+const MAGIC: &[u8; 4] = b"RMCE";
+const VERSION: u8 = 1;
+const DOMAIN: &[u8] = b"rmce-seal-v1";
+const HEADER_LEN: usize = MAGIC.len() + 1 + ShareableSecret::SIZE;
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Length a sealed blob adds to the plaintext.
+pub const SEAL_OVERHEAD: usize = HEADER_LEN + TAG_LEN;
+
+/// Encrypts `plaintext` to `pk`, authenticating `aad` along with it.
+#[cfg(feature = "openssl")]
+pub fn seal(pk: &PublicKey, plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+  seal_with_entropy_provider(pk, plaintext, aad, crate::crypto_random)
+}
+
+pub fn seal_with_entropy_provider<F: FnMut(&mut [u8])>(
+  pk: &PublicKey,
+  plaintext: &[u8],
+  aad: &[u8],
+  entropy_provider: F,
+) -> Vec<u8> {
+  let (enc, key) = pk.encapsulate_with_entropy_provider(entropy_provider);
+
+  let mut blob = Vec::with_capacity(SEAL_OVERHEAD + plaintext.len());
+  blob.extend_from_slice(MAGIC);
+  blob.push(VERSION);
+  blob.extend_from_slice(enc.as_bytes());
+
+  let (cipher, nonce) = cipher(&key, &blob);
+  let ciphertext = cipher
+    .encrypt(
+      (&nonce).into(),
+      Payload {
+        msg: plaintext,
+        aad: &[&blob[..], aad].concat(),
+      },
+    )
+    .unwrap();
+  blob.extend_from_slice(&ciphertext);
+  blob
+}
+
+/// Decrypts a blob produced by `seal`. `aad` must be the same as when sealing.
+pub fn open(sk: &SecretKey, blob: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+  open_with(blob, aad, |enc| enc.try_decapsulate(sk))
+}
+
+pub fn open_expanded(sk: &ExpandedSecretKey, blob: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+  open_with(blob, aad, |enc| enc.try_decapsulate_expanded(sk))
+}
+
+fn open_with(
+  blob: &[u8],
+  aad: &[u8],
+  decapsulate: impl FnOnce(&ShareableSecret) -> Result<SharedKey, Error>,
+) -> Result<Vec<u8>, Error> {
+  if blob.len() < SEAL_OVERHEAD {
+    return Err(Error::InvalidLength {
+      got: blob.len(),
+      expected: SEAL_OVERHEAD,
+    });
+  }
+  let (header, ciphertext) = blob.split_at(HEADER_LEN);
+  if &header[..MAGIC.len()] != MAGIC || header[MAGIC.len()] != VERSION {
+    return Err(Error::InvalidHeader);
+  }
+  let enc = ShareableSecret::try_from(&header[MAGIC.len() + 1..])?;
+  let key = decapsulate(&enc)?;
+
+  let (cipher, nonce) = cipher(&key, header);
+  cipher
+    .decrypt(
+      (&nonce).into(),
+      Payload {
+        msg: ciphertext,
+        aad: &[header, aad].concat(),
+      },
+    )
+    .map_err(|_| Error::DecryptionFailed)
+}
+
+fn cipher(key: &SharedKey, header: &[u8]) -> (ChaCha20Poly1305, [u8; NONCE_LEN]) {
+  let mut okm = Zeroizing::new([0u8; KEY_LEN + NONCE_LEN]);
+  let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+  hash.absorb(DOMAIN);
+  hash.absorb(key.as_bytes());
+  hash.absorb(header);
+  hash.squeeze(okm.as_mut());
+
+  let cipher = ChaCha20Poly1305::new_from_slice(&okm[..KEY_LEN]).unwrap();
+  (cipher, okm[KEY_LEN..].try_into().unwrap())
+}
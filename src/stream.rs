@@ -0,0 +1,257 @@
+//! Chunked encryption of data streams to a public key, for inputs too large to
+//! hold in memory.
+//!
+//! # Format (version 1)
+//!
+//! ```text
+//! header = "RMCS" || version (0x01) || ShareableSecret
+//! body   = chunk_0 || chunk_1 || ... || chunk_n
+//! ```
+//!
+//! The ChaCha20-Poly1305 key is 32 bytes of SHAKE256 over `"rmce-stream-v1"`,
+//! the [`crate::SharedKey`] and the header. The plaintext is split into chunks
+//! of [`CHUNK_LEN`] bytes, each sealed with no associated data and followed by
+//! its 16-byte tag. Only the last chunk may be shorter, and it is empty only
+//! when the whole plaintext is.
+//!
+//! Following the STREAM construction, the nonce of chunk `i` is `i` as an
+//! 11-byte big-endian integer, followed by `0x01` for the last chunk and `0x00`
+//! for the others. Reordered or dropped chunks then fail to decrypt, and so
+//! does a stream cut at a chunk boundary, because its new last chunk was not
+//! sealed as the last one.
+
+use std::io::{self, Read, Write};
+
+use chacha20poly1305::{
+  aead::{AeadInPlace, KeyInit},
+  ChaCha20Poly1305,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+  impls::libkeccak::{Sponge, SHAKE256_RATE, SHAKE_SUFFIX},
+  Error, ExpandedSecretKey, PublicKey, SecretKey, ShareableSecret, SharedKey,
+};
+
+const MAGIC: &[u8; 4] = b"RMCS";
+const VERSION: u8 = 1;
+const DOMAIN: &[u8] = b"rmce-stream-v1";
+const HEADER_LEN: usize = MAGIC.len() + 1 + ShareableSecret::SIZE;
+const TAG_LEN: usize = 16;
+
+/// Length of the plaintext of every chunk but the last.
+pub const CHUNK_LEN: usize = 64 * 1024;
+const SEALED_CHUNK_LEN: usize = CHUNK_LEN + TAG_LEN;
+
+struct Stream {
+  cipher: ChaCha20Poly1305,
+  counter: u64,
+}
+
+impl Stream {
+  fn new(key: &SharedKey, header: &[u8]) -> Self {
+    let mut okm = Zeroizing::new([0u8; 32]);
+    let mut hash = Sponge::new(SHAKE256_RATE, SHAKE_SUFFIX);
+    hash.absorb(DOMAIN);
+    hash.absorb(key.as_bytes());
+    hash.absorb(header);
+    hash.squeeze(okm.as_mut());
+    Self {
+      cipher: ChaCha20Poly1305::new_from_slice(okm.as_ref()).unwrap(),
+      counter: 0,
+    }
+  }
+  fn nonce(&mut self, last: bool) -> io::Result<[u8; 12]> {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&self.counter.to_be_bytes());
+    nonce[11] = last as u8;
+    self.counter = self
+      .counter
+      .checked_add(1)
+      .ok_or_else(|| invalid_data(Error::MessageLimitReached))?;
+    Ok(nonce)
+  }
+  fn seal(&mut self, chunk: &mut Vec<u8>, last: bool) -> io::Result<()> {
+    let nonce = self.nonce(last)?;
+    self
+      .cipher
+      .encrypt_in_place((&nonce).into(), &[], chunk)
+      .unwrap();
+    Ok(())
+  }
+  fn open(&mut self, chunk: &mut Vec<u8>, last: bool) -> io::Result<()> {
+    let nonce = self.nonce(last)?;
+    self
+      .cipher
+      .decrypt_in_place((&nonce).into(), &[], chunk)
+      .map_err(|_| invalid_data(Error::DecryptionFailed))
+  }
+}
+
+fn invalid_data(err: Error) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Encrypts everything written to it into the inner writer.
+///
+/// Calling [`Encryptor::finish`] once all data is written is mandatory: it
+/// seals and writes the last chunk. Dropping an `Encryptor` without it leaves
+/// a stream without a last chunk, which fails to decrypt and cannot be told
+/// apart from an interrupted write.
+#[must_use = "the stream is truncated unless `Encryptor::finish` is called"]
+pub struct Encryptor<W: Write> {
+  inner: W,
+  stream: Stream,
+  buf: Vec<u8>,
+}
+
+impl<W: Write> Encryptor<W> {
+  /// Writes the header to `inner`.
+  #[cfg(feature = "openssl")]
+  #[must_use = "the stream is truncated unless `Encryptor::finish` is called"]
+  pub fn new(pk: &PublicKey, inner: W) -> io::Result<Self> {
+    Self::with_entropy_provider(pk, inner, crate::crypto_random)
+  }
+  #[must_use = "the stream is truncated unless `Encryptor::finish` is called"]
+  pub fn with_entropy_provider<F: FnMut(&mut [u8])>(
+    pk: &PublicKey,
+    mut inner: W,
+    entropy_provider: F,
+  ) -> io::Result<Self> {
+    let (enc, key) = pk.encapsulate_with_entropy_provider(entropy_provider);
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(VERSION);
+    header.extend_from_slice(enc.as_bytes());
+    inner.write_all(&header)?;
+    Ok(Self {
+      inner,
+      stream: Stream::new(&key, &header),
+      buf: Vec::with_capacity(SEALED_CHUNK_LEN),
+    })
+  }
+  /// Seals and writes the last chunk, flushes and returns the inner writer.
+  /// Must be called, even when nothing was written.
+  pub fn finish(mut self) -> io::Result<W> {
+    self.stream.seal(&mut self.buf, true)?;
+    self.inner.write_all(&self.buf)?;
+    self.buf.clear();
+    self.inner.flush()?;
+    Ok(self.inner)
+  }
+}
+
+impl<W: Write> Write for Encryptor<W> {
+  fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+    if data.is_empty() {
+      return Ok(0);
+    }
+    /* a full chunk is only known not to be the last one when more data comes */
+    if self.buf.len() == CHUNK_LEN {
+      self.stream.seal(&mut self.buf, false)?;
+      self.inner.write_all(&self.buf)?;
+      self.buf.clear();
+    }
+    let len = data.len().min(CHUNK_LEN - self.buf.len());
+    self.buf.extend_from_slice(&data[..len]);
+    Ok(len)
+  }
+  /// Flushes the inner writer. Data of the current chunk stays buffered until
+  /// the chunk is full or the stream is finished.
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+/// Decrypts a stream produced by [`Encryptor`], failing with
+/// [`io::ErrorKind::InvalidData`] as soon as a chunk does not authenticate.
+/// Once that happens, every later read fails too.
+pub struct Decryptor<R: Read> {
+  inner: R,
+  stream: Stream,
+  sealed: Vec<u8>,
+  buf: Vec<u8>,
+  pos: usize,
+  finished: bool,
+  failed: bool,
+}
+
+impl<R: Read> Decryptor<R> {
+  /// Reads the header from `inner`.
+  pub fn new(sk: &SecretKey, inner: R) -> io::Result<Self> {
+    Self::new_with(inner, |enc| enc.try_decapsulate(sk))
+  }
+  pub fn new_expanded(sk: &ExpandedSecretKey, inner: R) -> io::Result<Self> {
+    Self::new_with(inner, |enc| enc.try_decapsulate_expanded(sk))
+  }
+  fn new_with(
+    mut inner: R,
+    decapsulate: impl FnOnce(&ShareableSecret) -> Result<SharedKey, Error>,
+  ) -> io::Result<Self> {
+    let mut header = [0u8; HEADER_LEN];
+    inner.read_exact(&mut header)?;
+    if &header[..MAGIC.len()] != MAGIC || header[MAGIC.len()] != VERSION {
+      return Err(invalid_data(Error::InvalidHeader));
+    }
+    let enc = ShareableSecret::try_from(&header[MAGIC.len() + 1..]).map_err(invalid_data)?;
+    let key = decapsulate(&enc).map_err(invalid_data)?;
+    Ok(Self {
+      inner,
+      stream: Stream::new(&key, &header),
+      sealed: Vec::with_capacity(SEALED_CHUNK_LEN + 1),
+      buf: Vec::with_capacity(SEALED_CHUNK_LEN + 1),
+      pos: 0,
+      finished: false,
+      failed: false,
+    })
+  }
+  /* reads one byte past a full chunk to tell whether it is the last one */
+  fn next_chunk(&mut self) -> io::Result<()> {
+    while self.sealed.len() <= SEALED_CHUNK_LEN {
+      let filled = self.sealed.len();
+      self.sealed.resize(SEALED_CHUNK_LEN + 1, 0);
+      let read = self.inner.read(&mut self.sealed[filled..]);
+      let n = *read.as_ref().unwrap_or(&0);
+      self.sealed.truncate(filled + n);
+      match read {
+        Ok(0) => break,
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+        Err(e) => return Err(e),
+      }
+    }
+
+    let last = self.sealed.len() <= SEALED_CHUNK_LEN;
+    let carry = if last { None } else { self.sealed.pop() };
+    std::mem::swap(&mut self.buf, &mut self.sealed);
+    self.sealed.clear();
+    self.sealed.extend(carry);
+    self.pos = 0;
+    self.finished = last;
+
+    let opened = self.stream.open(&mut self.buf, last);
+    if opened.is_err() {
+      self.buf.clear();
+      self.failed = true;
+    }
+    opened
+  }
+}
+
+impl<R: Read> Read for Decryptor<R> {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    while self.pos == self.buf.len() {
+      if self.failed {
+        return Err(invalid_data(Error::DecryptionFailed));
+      }
+      if self.finished {
+        return Ok(0);
+      }
+      self.next_chunk()?;
+    }
+    let len = (self.buf.len() - self.pos).min(out.len());
+    out[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+    self.pos += len;
+    Ok(len)
+  }
+}
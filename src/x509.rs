@@ -0,0 +1,133 @@
+//! X.509 certificates carrying a [`PublicKey`], for KEMTLS-style
+//! authentication by decapsulation instead of signatures.
+//!
+//! Classic McEliece keys cannot sign, so certificates are signed by the
+//! issuer's key of another algorithm through [`CertificateSigner`]. The subject
+//! public key is encoded as in [`crate::asn1`], and [`CertificateBuilder`] adds a
+//! critical keyUsage extension allowing only keyEncipherment.
+//!
+//! Extracting the key with [`public_key`] does not verify the certificate:
+//! checking the issuer's signature, the validity period and the chain is left
+//! to the caller's PKI code.
+
+use der::{asn1::BitString, referenced::OwnedToRef, Decode, DecodePem, Encode};
+pub use x509_cert::{
+  self, der,
+  ext::{pkix::KeyUsages, AsExtension, Extension},
+  name::Name,
+  serial_number::SerialNumber,
+  time::{Time, Validity},
+  Certificate, TbsCertificate, Version,
+};
+use x509_cert::{
+  ext::pkix::KeyUsage,
+  spki::{self, AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+};
+
+use crate::{asn1::OID, Error, PublicKey};
+
+/// Signs TBSCertificates with the issuer's key.
+pub trait CertificateSigner {
+  /// `AlgorithmIdentifier` written to the `signature` field of the
+  /// TBSCertificate and to the `signatureAlgorithm` of the certificate.
+  fn signature_algorithm(&self) -> AlgorithmIdentifierOwned;
+  /// Signs the DER encoding of the TBSCertificate. Failures should be
+  /// reported as [`Error::SigningFailed`].
+  fn sign(&self, tbs_certificate: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Version 3 certificate for a [`PublicKey`].
+#[derive(Debug, Clone)]
+pub struct CertificateBuilder {
+  serial_number: SerialNumber,
+  validity: Validity,
+  issuer: Name,
+  subject: Name,
+  subject_public_key_info: SubjectPublicKeyInfoOwned,
+  extensions: Vec<Extension>,
+}
+
+impl CertificateBuilder {
+  pub fn new(
+    pk: &PublicKey,
+    serial_number: SerialNumber,
+    validity: Validity,
+    issuer: Name,
+    subject: Name,
+  ) -> Result<Self, Error> {
+    let subject_public_key_info = SubjectPublicKeyInfoOwned {
+      algorithm: AlgorithmIdentifierOwned {
+        oid: OID,
+        parameters: None,
+      },
+      subject_public_key: BitString::from_bytes(pk.as_bytes())
+        .map_err(|_| Error::InvalidEncoding)?,
+    };
+    let mut builder = Self {
+      serial_number,
+      validity,
+      issuer,
+      subject,
+      subject_public_key_info,
+      extensions: Vec::new(),
+    };
+    builder.add_extension(&KeyUsage(KeyUsages::KeyEncipherment.into()))?;
+    Ok(builder)
+  }
+  /// Appends an extension, with the criticality the extension type asks for.
+  pub fn add_extension<E: AsExtension>(&mut self, extension: &E) -> Result<(), Error> {
+    let extension = extension
+      .to_extension(&self.subject, &self.extensions)
+      .map_err(|_| Error::InvalidEncoding)?;
+    self.extensions.push(extension);
+    Ok(())
+  }
+  pub fn tbs_certificate(&self, signature: AlgorithmIdentifierOwned) -> TbsCertificate {
+    TbsCertificate {
+      version: Version::V3,
+      serial_number: self.serial_number.clone(),
+      signature,
+      issuer: self.issuer.clone(),
+      validity: self.validity,
+      subject: self.subject.clone(),
+      subject_public_key_info: self.subject_public_key_info.clone(),
+      issuer_unique_id: None,
+      subject_unique_id: None,
+      extensions: Some(self.extensions.clone()),
+    }
+  }
+  pub fn sign<S: CertificateSigner + ?Sized>(&self, signer: &S) -> Result<Certificate, Error> {
+    let signature_algorithm = signer.signature_algorithm();
+    let tbs_certificate = self.tbs_certificate(signature_algorithm.clone());
+    let tbs = tbs_certificate
+      .to_der()
+      .map_err(|_| Error::InvalidEncoding)?;
+    let signature = signer.sign(&tbs)?;
+    Ok(Certificate {
+      tbs_certificate,
+      signature_algorithm,
+      signature: BitString::from_bytes(&signature).map_err(|_| Error::InvalidEncoding)?,
+    })
+  }
+}
+
+/// Extracts the subject public key, failing with [`Error::AlgorithmMismatch`]
+/// when the certificate is for a key of another algorithm or parameter set.
+pub fn public_key(certificate: &Certificate) -> Result<PublicKey, Error> {
+  let spki = certificate
+    .tbs_certificate
+    .subject_public_key_info
+    .owned_to_ref();
+  PublicKey::try_from(spki).map_err(|err| match err {
+    spki::Error::OidUnknown { .. } => Error::AlgorithmMismatch,
+    _ => Error::InvalidPublicKey,
+  })
+}
+
+pub fn public_key_from_der(der: &[u8]) -> Result<PublicKey, Error> {
+  public_key(&Certificate::from_der(der).map_err(|_| Error::InvalidEncoding)?)
+}
+
+pub fn public_key_from_pem(pem: &str) -> Result<PublicKey, Error> {
+  public_key(&Certificate::from_pem(pem).map_err(|_| Error::InvalidEncoding)?)
+}
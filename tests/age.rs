@@ -0,0 +1,249 @@
+#![cfg(feature = "age")]
+
+mod common;
+
+use common::{counter, keypair};
+use rmce::age::*;
+use rmce::Error;
+
+#[test]
+fn age_encodings_round_trip() {
+  let (pk, sk) = keypair(0);
+
+  let recipient = encode_recipient(&pk);
+  assert!(recipient.starts_with("age1mceliece1"));
+  assert_eq!(decode_recipient(&recipient).unwrap(), pk);
+  assert_eq!(decode_recipient(&recipient.to_uppercase()).unwrap(), pk);
+
+  let identity = encode_identity(&sk);
+  assert!(identity.starts_with("AGE-PLUGIN-MCELIECE-1"));
+  assert_eq!(decode_identity(&identity).unwrap(), sk);
+
+  assert!(matches!(
+    decode_recipient(&identity),
+    Err(Error::InvalidEncoding)
+  ));
+  assert!(matches!(
+    decode_identity(&recipient),
+    Err(Error::InvalidEncoding)
+  ));
+  let mut typo = recipient.clone().into_bytes();
+  typo[100] = if typo[100] == b'q' { b'p' } else { b'q' };
+  assert!(matches!(
+    decode_recipient(std::str::from_utf8(&typo).unwrap()),
+    Err(Error::InvalidEncoding)
+  ));
+}
+
+#[test]
+fn age_file_key_wrapping() {
+  let (pk, sk) = keypair(1);
+  let (_, other_sk) = keypair(2);
+  let (esk, other_esk) = (sk.expand(), other_sk.expand());
+  let file_key = [7u8; FILE_KEY_LEN];
+
+  let stanza = wrap_file_key_with_entropy_provider(&pk, &file_key, counter(0));
+  assert_eq!(stanza.tag, STANZA_TAG);
+  assert_eq!(stanza.args.len(), 1);
+  assert_eq!(stanza.body.len(), 32);
+  assert_eq!(*unwrap_file_key(&esk, &stanza).unwrap(), file_key);
+  assert!(matches!(
+    unwrap_file_key(&other_esk, &stanza),
+    Err(Error::DecryptionFailed)
+  ));
+
+  let mut flipped = stanza.clone();
+  flipped.body[0] ^= 1;
+  assert!(matches!(
+    unwrap_file_key(&esk, &flipped),
+    Err(Error::DecryptionFailed)
+  ));
+  let mut padded = stanza.clone();
+  padded.args[0].push_str("==");
+  let mut extra_arg = stanza.clone();
+  extra_arg.args.push("x".to_owned());
+  let mut short = stanza.clone();
+  short.body.pop();
+  let mut other_tag = stanza.clone();
+  other_tag.tag = "X25519".to_owned();
+  for malformed in [padded, extra_arg, short, other_tag] {
+    assert!(matches!(
+      unwrap_file_key(&esk, &malformed),
+      Err(Error::InvalidHeader)
+    ));
+  }
+}
+
+#[cfg(feature = "age-plugin")]
+mod plugin {
+  use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    io::Write,
+    iter,
+    path::Path,
+    process::{Command, Stdio},
+  };
+
+  use base64::{engine::general_purpose::STANDARD_NO_PAD as BASE64, Engine};
+
+  use super::*;
+
+  /* the client's answers to the plugin's commands are queued up front */
+  fn run(phase: &str, input: String) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_age-plugin-mceliece"))
+      .arg(format!("--age-plugin={phase}"))
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let out = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+  }
+
+  fn stanza(tag_and_args: &str, body: &[u8]) -> String {
+    let mut stanza = format!("-> {tag_and_args}\n");
+    let body = BASE64.encode(body);
+    for line in body.as_bytes().chunks(64) {
+      stanza.push_str(std::str::from_utf8(line).unwrap());
+      stanza.push('\n');
+    }
+    if body.len().is_multiple_of(64) {
+      stanza.push('\n');
+    }
+    stanza
+  }
+
+  /* splits the plugin's output into (command line, base64 body) pairs */
+  fn parse(output: &str) -> Vec<(&str, String)> {
+    let mut lines = output.lines();
+    let mut commands = Vec::new();
+    while let Some(header) = lines.next() {
+      let mut body = String::new();
+      for line in lines.by_ref() {
+        body.push_str(line);
+        if line.len() < 64 {
+          break;
+        }
+      }
+      commands.push((header.strip_prefix("-> ").unwrap(), body));
+    }
+    commands
+  }
+
+  #[test]
+  fn age_plugin_state_machines() {
+    let (pk, sk) = keypair(3);
+    let file_key = [0x42u8; FILE_KEY_LEN];
+    let ok = stanza("ok", &[]);
+
+    let output = run(
+      "recipient-v1",
+      [
+        stanza(&format!("add-recipient {}", encode_recipient(&pk)), &[]),
+        stanza("extension-labels", &[]),
+        stanza("grease-[ x y", b"ignored"),
+        stanza("wrap-file-key", &file_key),
+        stanza("done", &[]),
+        ok.repeat(2),
+      ]
+      .concat(),
+    );
+    let commands = parse(&output);
+    assert_eq!(commands.len(), 3, "{output}");
+    assert_eq!(commands[0], ("labels postquantum", String::new()));
+    let args: Vec<&str> = commands[1].0.split(' ').collect();
+    assert_eq!(args[..3], ["recipient-stanza", "0", STANZA_TAG]);
+    assert_eq!(commands[2].0, "done");
+    let wrapped = Stanza {
+      tag: STANZA_TAG.to_owned(),
+      args: vec![args[3].to_owned()],
+      body: BASE64.decode(&commands[1].1).unwrap(),
+    };
+
+    let output = run(
+      "identity-v1",
+      [
+        stanza(&format!("add-identity {}", *encode_identity(&sk)), &[]),
+        stanza("recipient-stanza 0 X25519 abcd", &[1; 32]),
+        stanza(
+          &format!("recipient-stanza 0 {STANZA_TAG} {}", wrapped.args[0]),
+          &wrapped.body,
+        ),
+        stanza("done", &[]),
+        ok.clone(),
+      ]
+      .concat(),
+    );
+    assert_eq!(
+      parse(&output),
+      [
+        ("file-key 0", BASE64.encode(file_key)),
+        ("done", String::new())
+      ]
+    );
+
+    let output = run(
+      "recipient-v1",
+      [
+        stanza("add-recipient age1mceliece1qqqqqqqqqqqqqqqqqqq", &[]),
+        stanza("wrap-file-key", &file_key),
+        stanza("done", &[]),
+        ok,
+      ]
+      .concat(),
+    );
+    let commands = parse(&output);
+    assert_eq!(commands.len(), 2, "{output}");
+    assert_eq!(commands[0].0, "error recipient 0");
+    assert_eq!(commands[1].0, "done");
+  }
+
+  /* needs an `age` or `rage` client; pick one with AGE_CLIENT, run with --ignored */
+  #[test]
+  #[ignore = "requires an age client on PATH"]
+  fn age_client_round_trip() {
+    let (pk, sk) = keypair(4);
+    let client = std::env::var("AGE_CLIENT").unwrap_or_else(|_| "age".to_owned());
+    let plugin = Path::new(env!("CARGO_BIN_EXE_age-plugin-mceliece"));
+    let path = env::join_paths(
+      iter::once(plugin.parent().unwrap().to_owned())
+        .chain(env::split_paths(&env::var_os("PATH").unwrap_or_default())),
+    )
+    .unwrap();
+    let dir = env::temp_dir().join(format!("rmce-age-client-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let recipients = dir.join("recipients.txt");
+    let identities = dir.join("identities.txt");
+    fs::write(&recipients, format!("{}\n", encode_recipient(&pk))).unwrap();
+    fs::write(&identities, format!("{}\n", *encode_identity(&sk))).unwrap();
+
+    let age = |args: &[&OsStr], input: &[u8]| {
+      let mut child = Command::new(&client)
+        .args(args)
+        .env("PATH", &path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+      let mut stdin = child.stdin.take().unwrap();
+      let input = input.to_vec();
+      let writer = std::thread::spawn(move || stdin.write_all(&input));
+      let out = child.wait_with_output().unwrap();
+      writer.join().unwrap().unwrap();
+      assert!(out.status.success());
+      out.stdout
+    };
+
+    let plaintext = b"post-quantum hello through the plugin protocol";
+    let encrypted = age(&["-e".as_ref(), "-R".as_ref(), recipients.as_ref()], plaintext);
+    let decrypted = age(&["-d".as_ref(), "-i".as_ref(), identities.as_ref()], &encrypted);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(decrypted, plaintext);
+  }
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "cms")]
+
+mod common;
+
+use common::{counter, keypair};
+use rmce::cms::{
+  cms::{cert::x509::ext::pkix::SubjectKeyIdentifier, content_info::ContentInfo},
+  *,
+};
+use rmce::{
+  asn1::{der::asn1::OctetString, der::Decode, der::Encode, OID},
+  Error,
+};
+
+fn rid(id: u8) -> RecipientIdentifier {
+  RecipientIdentifier::SubjectKeyIdentifier(SubjectKeyIdentifier(
+    OctetString::new([id; 20]).unwrap(),
+  ))
+}
+
+fn kem_recipient_infos(recip_infos: &RecipientInfos) -> Vec<KemRecipientInfo> {
+  recip_infos
+    .0
+    .iter()
+    .map(|info| match info {
+      RecipientInfo::Ori(ori) => {
+        assert_eq!(ori.ori_type, ID_ORI_KEM);
+        ori.ori_value.decode_as().unwrap()
+      }
+      _ => panic!("not an ori recipient info"),
+    })
+    .collect()
+}
+
+#[test]
+fn cms_envelope_round_trip() {
+  let (pk0, sk0) = keypair(0);
+  let (pk1, sk1) = keypair(1);
+  let recipients = [
+    Recipient {
+      public_key: &pk0,
+      rid: rid(0),
+    },
+    Recipient {
+      public_key: &pk1,
+      rid: rid(1),
+    },
+  ];
+  let content = b"Subject: hello\r\n\r\npost-quantum mail\r\n";
+
+  let enveloped = envelope_with_entropy_provider(&recipients, content, counter(0)).unwrap();
+  assert_eq!(enveloped.content_type, ID_ENVELOPED_DATA);
+  let der = enveloped.to_der().unwrap();
+  let data: EnvelopedData = ContentInfo::from_der(&der)
+    .unwrap()
+    .content
+    .decode_as()
+    .unwrap();
+  assert_eq!(data.version, CmsVersion::V3);
+  assert_eq!(data.encrypted_content.content_enc_alg.oid, ID_AES256_CBC);
+  let infos = kem_recipient_infos(&data.recip_infos);
+  assert_eq!(infos.len(), 2);
+  for info in &infos {
+    assert_eq!(info.version, CmsVersion::V0);
+    assert_eq!(info.kem.oid, OID);
+    assert_eq!(info.kdf.oid, ID_ALG_HKDF_WITH_SHA256);
+    assert_eq!(info.kek_length, 32);
+    assert_eq!(info.wrap.oid, ID_AES256_WRAP);
+    assert_eq!(info.kemct.as_bytes().len(), rmce::ShareableSecret::SIZE);
+    assert_eq!(info.encrypted_key.as_bytes().len(), 40);
+  }
+
+  assert_eq!(decrypt_der(&sk0, &rid(0), &der).unwrap(), content);
+  assert_eq!(decrypt(&sk1, &rid(1), &enveloped).unwrap(), content);
+  assert!(matches!(
+    decrypt(&sk0, &rid(2), &enveloped),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    decrypt(&sk1, &rid(0), &enveloped),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    decrypt_der(&sk0, &rid(0), &der[..der.len() - 1]),
+    Err(Error::InvalidEncoding)
+  ));
+
+  let auth_enveloped =
+    auth_envelope_with_entropy_provider(&recipients, content, counter(1)).unwrap();
+  assert_eq!(auth_enveloped.content_type, ID_CT_AUTH_ENVELOPED_DATA);
+  assert_eq!(decrypt(&sk0, &rid(0), &auth_enveloped).unwrap(), content);
+  assert_eq!(decrypt(&sk1, &rid(1), &auth_enveloped).unwrap(), content);
+
+  let mut data: AuthEnvelopedData = auth_enveloped.content.decode_as().unwrap();
+  assert_eq!(data.version, CmsVersion::V0);
+  assert_eq!(data.mac.as_bytes().len(), 16);
+  let mut mac = data.mac.as_bytes().to_vec();
+  mac[0] ^= 1;
+  data.mac = OctetString::new(mac).unwrap();
+  let tampered = ContentInfo {
+    content_type: ID_CT_AUTH_ENVELOPED_DATA,
+    content: rmce::asn1::der::Any::encode_from(&data).unwrap(),
+  };
+  assert!(matches!(
+    decrypt(&sk0, &rid(0), &tampered),
+    Err(Error::DecryptionFailed)
+  ));
+
+  assert!(matches!(
+    envelope_with_entropy_provider(&[], content, counter(0)),
+    Err(Error::InvalidEncoding)
+  ));
+}
+
+#[test]
+fn cms_kem_recipient_info() {
+  let (pk, sk) = keypair(2);
+  let esk = sk.expand();
+  let content_key = [0x5au8; 32];
+
+  let info = recipient_info_with_entropy_provider(
+    &pk,
+    rid(7),
+    &content_key,
+    Some(b"user keying material"),
+    counter(3),
+  )
+  .unwrap();
+  let mut info: KemRecipientInfo = match info {
+    RecipientInfo::Ori(ori) => ori.ori_value.decode_as().unwrap(),
+    _ => panic!("not an ori recipient info"),
+  };
+  assert_eq!(*unwrap_content_key(&esk, &info).unwrap(), content_key);
+
+  /* the ukm is bound into the key-encryption key */
+  let ukm = info.ukm.take();
+  assert!(matches!(
+    unwrap_content_key(&esk, &info),
+    Err(Error::DecryptionFailed)
+  ));
+  info.ukm = ukm;
+
+  let mut other_kdf = info.clone();
+  other_kdf.kdf.oid = ID_AES256_WRAP;
+  let mut other_kem = info.clone();
+  other_kem.kem.oid = rmce::asn1::ParameterSet::Mceliece6688128f.oid();
+  let mut other_length = info.clone();
+  other_length.kek_length = 16;
+  for other in [other_kdf, other_kem, other_length] {
+    assert!(matches!(
+      unwrap_content_key(&esk, &other),
+      Err(Error::AlgorithmMismatch)
+    ));
+  }
+  info.kemct = OctetString::new(&info.kemct.as_bytes()[1..]).unwrap();
+  assert!(matches!(
+    unwrap_content_key(&esk, &info),
+    Err(Error::InvalidLength { .. })
+  ));
+
+  assert!(matches!(
+    recipient_info_with_entropy_provider(&pk, rid(7), &[0u8; 20], None, counter(3)),
+    Err(Error::InvalidLength { got: 20, .. })
+  ));
+}
+
+/* the KDF, the key wrap and the content encryption redone with OpenSSL */
+#[cfg(feature = "openssl")]
+#[test]
+fn cms_matches_openssl() {
+  use openssl::{
+    aes::{unwrap_key, AesKey},
+    md::Md,
+    pkey::Id,
+    pkey_ctx::PkeyCtx,
+    symm::{decrypt as symm_decrypt, Cipher},
+  };
+
+  let (pk, sk) = keypair(3);
+  let content = b"0123456789abcdef0123456789abcdef";
+  let enveloped = envelope(
+    &[Recipient {
+      public_key: &pk,
+      rid: rid(3),
+    }],
+    content,
+  )
+  .unwrap();
+  let data: EnvelopedData = enveloped.content.decode_as().unwrap();
+  let info = kem_recipient_infos(&data.recip_infos).remove(0);
+
+  let enc = rmce::ShareableSecret::try_from(info.kemct.as_bytes()).unwrap();
+  let key = enc.decapsulate(&sk);
+  let other_info = CmsOriForKemOtherInfo {
+    wrap: info.wrap.clone(),
+    kek_length: info.kek_length,
+    ukm: None,
+  }
+  .to_der()
+  .unwrap();
+  let mut kek = [0u8; 32];
+  let mut ctx = PkeyCtx::new_id(Id::HKDF).unwrap();
+  ctx.derive_init().unwrap();
+  ctx.set_hkdf_md(Md::sha256()).unwrap();
+  ctx.set_hkdf_key(key.as_bytes()).unwrap();
+  ctx.add_hkdf_info(&other_info).unwrap();
+  ctx.derive(Some(&mut kek)).unwrap();
+
+  let mut content_key = [0u8; 32];
+  unwrap_key(
+    &AesKey::new_decrypt(&kek).unwrap(),
+    None,
+    &mut content_key,
+    info.encrypted_key.as_bytes(),
+  )
+  .unwrap();
+
+  let encrypted = &data.encrypted_content;
+  let iv: OctetString = encrypted
+    .content_enc_alg
+    .parameters
+    .as_ref()
+    .unwrap()
+    .decode_as()
+    .unwrap();
+  let decrypted = symm_decrypt(
+    Cipher::aes_256_cbc(),
+    &content_key,
+    Some(iv.as_bytes()),
+    encrypted.encrypted_content.as_ref().unwrap().as_bytes(),
+  )
+  .unwrap();
+  assert_eq!(decrypted, content);
+}
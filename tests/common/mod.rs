@@ -0,0 +1,22 @@
+#![allow(dead_code)]
+
+use rmce::{generate_keypair_with_entropy_provider, PublicKey, SecretKey};
+
+/// Key pair `i` of the known-answer tests.
+pub fn keypair(i: usize) -> (PublicKey, SecretKey) {
+  let keypair_randombytes = include!("../resources/keypair_randombytes.in");
+  generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[i]);
+  })
+}
+
+/// Deterministic entropy provider: hands out `start`, `start + 1`, ... wrapping.
+pub fn counter(start: u8) -> impl FnMut(&mut [u8]) {
+  let mut n = start;
+  move |data| {
+    for b in data {
+      *b = n;
+      n = n.wrapping_add(1);
+    }
+  }
+}
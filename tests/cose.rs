@@ -0,0 +1,212 @@
+#![cfg(feature = "cose")]
+
+mod common;
+
+use common::{counter, keypair};
+use rmce::cose::{
+  coset::{
+    cbor::value::Value, iana, Algorithm, CborSerializable, KeyType, Label, TaggedCborSerializable,
+  },
+  *,
+};
+use rmce::{Error, ParameterSet};
+
+#[test]
+fn cose_key_round_trip() {
+  let (pk, sk) = keypair(0);
+  let (other_pk, _) = keypair(1);
+
+  let cbor = encode_public_key(&pk);
+  let key = CoseKey::from_slice(&cbor).unwrap();
+  assert_eq!(key.kty, KeyType::Assigned(iana::KeyType::AKP));
+  assert_eq!(key.alg, Some(Algorithm::PrivateUse(-65546)));
+  assert_eq!(decode_public_key(&cbor).unwrap(), pk);
+  let cbor = encode_secret_key(&sk).unwrap();
+  assert_eq!(decode_secret_key(&cbor).unwrap(), sk);
+  assert_eq!(decode_public_key(&cbor).unwrap(), pk);
+  assert!(matches!(
+    decode_secret_key(&encode_public_key(&pk)),
+    Err(Error::InvalidEncoding)
+  ));
+  assert!(matches!(
+    decode_public_key(&cbor[..cbor.len() - 1]),
+    Err(Error::InvalidEncoding)
+  ));
+
+  let key = secret_key_to_cose_key(&sk).unwrap();
+  let mut other_set = key.clone();
+  other_set.alg = Some(Algorithm::PrivateUse(
+    ParameterSet::Mceliece6688128.cose_alg(),
+  ));
+  assert!(matches!(
+    public_key_from_cose_key(&other_set),
+    Err(Error::AlgorithmMismatch)
+  ));
+  let mut mismatched = key.clone();
+  mismatched.params[0].1 = Value::Bytes(other_pk.as_bytes().to_vec());
+  assert!(matches!(
+    secret_key_from_cose_key(&mismatched),
+    Err(Error::KeyPairMismatch)
+  ));
+  let mut okp = key.clone();
+  okp.kty = KeyType::Assigned(iana::KeyType::OKP);
+  assert!(matches!(
+    secret_key_from_cose_key(&okp),
+    Err(Error::InvalidEncoding)
+  ));
+
+  assert_eq!(
+    ParameterSet::from_cose_alg(-65546),
+    Some(ParameterSet::Mceliece8192128f)
+  );
+}
+
+#[test]
+fn cose_encrypt_round_trip() {
+  let (pk, sk) = keypair(2);
+  let (other_pk, other_sk) = keypair(3);
+  let plaintext = b"This is the content.";
+  let aad = b"external";
+
+  let message = encrypt_with_entropy_provider(&pk, plaintext, aad, Some(b"key-2"), counter(0));
+  let parsed = CoseEncrypt::from_tagged_slice(&message).unwrap();
+  assert_eq!(
+    parsed.protected.header.alg,
+    Some(Algorithm::Assigned(iana::Algorithm::A256GCM))
+  );
+  assert_eq!(parsed.unprotected.iv.len(), 12);
+  assert_eq!(parsed.recipients.len(), 1);
+  let recipient = &parsed.recipients[0];
+  assert_eq!(
+    recipient.protected.header.alg,
+    Some(Algorithm::PrivateUse(
+      ParameterSet::Mceliece8192128f.cose_alg()
+    ))
+  );
+  assert_eq!(recipient.unprotected.key_id, b"key-2");
+  assert_eq!(
+    recipient.ciphertext.as_ref().unwrap().len(),
+    rmce::ShareableSecret::SIZE
+  );
+
+  assert_eq!(decrypt(&sk, &message, aad).unwrap(), plaintext);
+  let untagged = parsed.clone().to_vec().unwrap();
+  assert_eq!(decrypt(&sk, &untagged, aad).unwrap(), plaintext);
+  assert!(matches!(
+    decrypt(&sk, &message, b"other"),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    decrypt(&other_sk, &message, aad),
+    Err(Error::DecryptionFailed)
+  ));
+
+  /* a second recipient is found by trial decryption */
+  let for_other = CoseEncrypt::from_tagged_slice(&encrypt_with_entropy_provider(
+    &other_pk,
+    plaintext,
+    aad,
+    None,
+    counter(1),
+  ))
+  .unwrap();
+  let mut both = parsed.clone();
+  both.recipients.push(for_other.recipients[0].clone());
+  assert!(matches!(
+    decrypt(&other_sk, &both.clone().to_vec().unwrap(), aad),
+    Err(Error::DecryptionFailed)
+  ));
+  assert_eq!(
+    decrypt(&sk, &both.to_vec().unwrap(), aad).unwrap(),
+    plaintext
+  );
+
+  let mut other_alg = parsed.clone();
+  other_alg.recipients[0].protected.header.alg = Some(Algorithm::PrivateUse(
+    ParameterSet::Mceliece6960119.cose_alg(),
+  ));
+  other_alg.recipients[0].protected.original_data = None;
+  assert!(matches!(
+    decrypt(&sk, &other_alg.to_vec().unwrap(), aad),
+    Err(Error::AlgorithmMismatch)
+  ));
+  let mut no_iv = parsed.clone();
+  no_iv.unprotected.iv.clear();
+  assert!(matches!(
+    decrypt(&sk, &no_iv.to_vec().unwrap(), aad),
+    Err(Error::InvalidHeader)
+  ));
+  let mut critical = parsed;
+  critical
+    .protected
+    .header
+    .rest
+    .push((Label::Int(-70000), Value::Null));
+  critical
+    .protected
+    .header
+    .crit
+    .push(coset::RegisteredLabelWithPrivate::PrivateUse(-70000));
+  critical.protected.original_data = None;
+  assert!(matches!(
+    decrypt(&sk, &critical.to_vec().unwrap(), aad),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    decrypt(&sk, &message[..message.len() - 1], aad),
+    Err(Error::InvalidHeader)
+  ));
+}
+
+/* the KDF context hand-encoded, and HKDF and the content decryption redone
+ * with OpenSSL */
+#[cfg(feature = "openssl")]
+#[test]
+fn cose_matches_openssl() {
+  use openssl::{md::Md, pkey::Id, pkey_ctx::PkeyCtx, symm};
+  use rmce::cose::coset::{enc_structure_data, EncryptionContext};
+
+  let (pk, sk) = keypair(4);
+  let plaintext = b"Four score and seven years ago";
+  let message = CoseEncrypt::from_tagged_slice(&encrypt(&pk, plaintext, &[], None)).unwrap();
+  let recipient = &message.recipients[0];
+  /* {1: -65546} */
+  let protected = [0xa1, 0x01, 0x3a, 0x00, 0x01, 0x00, 0x09];
+  assert_eq!(
+    recipient.protected.original_data.as_deref(),
+    Some(&protected[..])
+  );
+
+  /* [3, [null, null, null], [null, null, null], [256, h'a1013a00010009']] */
+  let mut context = vec![
+    0x84, 0x03, 0x83, 0xf6, 0xf6, 0xf6, 0x83, 0xf6, 0xf6, 0xf6, 0x82, 0x19, 0x01, 0x00, 0x47,
+  ];
+  context.extend_from_slice(&protected);
+  let enc = rmce::ShareableSecret::try_from(&recipient.ciphertext.as_ref().unwrap()[..]).unwrap();
+  let key = enc.decapsulate(&sk);
+  let mut content_key = [0u8; 32];
+  let mut ctx = PkeyCtx::new_id(Id::HKDF).unwrap();
+  ctx.derive_init().unwrap();
+  ctx.set_hkdf_md(Md::sha256()).unwrap();
+  ctx.set_hkdf_key(key.as_bytes()).unwrap();
+  ctx.add_hkdf_info(&context).unwrap();
+  ctx.derive(Some(&mut content_key)).unwrap();
+
+  let ciphertext = message.ciphertext.as_ref().unwrap();
+  let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 16);
+  let aad = enc_structure_data(
+    EncryptionContext::CoseEncrypt,
+    message.protected.clone(),
+    &[],
+  );
+  let decrypted = symm::decrypt_aead(
+    symm::Cipher::aes_256_gcm(),
+    &content_key,
+    Some(&message.unprotected.iv),
+    &aad,
+    ciphertext,
+    tag,
+  )
+  .unwrap();
+  assert_eq!(decrypted, plaintext);
+}
@@ -0,0 +1,98 @@
+use rmce::*;
+
+#[test]
+fn shared_key_matches_c_impl() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let results = include!("resources/results.in");
+  for ((k, e), r) in keypair_randombytes
+    .iter()
+    .zip(encrypt_randombytes)
+    .zip(results)
+  {
+    let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+      data.copy_from_slice(k);
+    });
+
+    let (ct, ss) = pk.encapsulate_with_entropy_provider(|data| {
+      data.copy_from_slice(&e);
+    });
+
+    let ss1 = ct.decapsulate(&sk);
+
+    assert_eq!(ss1, ss);
+    assert_eq!(ss.as_bytes(), &r);
+  }
+}
+
+#[cfg(feature = "openssl")]
+#[test]
+fn encapsulate_with_openssl_random() {
+  for _ in 0..3 {
+    let (pk, sk) = generate_keypair();
+
+    let (ct, ss) = pk.encapsulate();
+
+    assert_eq!(ct.decapsulate(&sk), ss);
+  }
+}
+
+#[test]
+fn altered_ciphertext_gives_unrelated_key() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[0]);
+  });
+  let (ct, ss) = pk.encapsulate_with_entropy_provider(|data| {
+    data.copy_from_slice(&encrypt_randombytes[0]);
+  });
+
+  assert_eq!(ct.try_decapsulate(&sk).unwrap(), ss);
+
+  let mut ct_altered: [u8; ShareableSecret::SIZE] = ct.into();
+  ct_altered[2] = ct_altered[2].wrapping_add(1);
+  let ct_altered: ShareableSecret = ct_altered.into();
+
+  let ss1 = ct_altered.decapsulate(&sk);
+  assert_eq!(ct_altered.try_decapsulate(&sk).unwrap(), ss1);
+  test_avalanche_effect(&ss, &ss1);
+}
+
+#[test]
+fn every_ciphertext_is_well_formed() {
+  // the syndrome has no padding bits, so validation has nothing to reject
+  for byte in [0x00, 0x5A, 0xFF] {
+    let ct = ShareableSecret::from([byte; ShareableSecret::SIZE]);
+    ct.validate().unwrap();
+  }
+}
+
+#[test]
+fn malformed_sharable_secret_input() {
+  let short = vec![0u8; ShareableSecret::SIZE - 1];
+  assert!(matches!(
+    ShareableSecret::try_from(short.as_slice()),
+    Err(Error::InvalidLength { got, expected }) if got == ShareableSecret::SIZE - 1 && expected == ShareableSecret::SIZE
+  ));
+}
+
+#[test]
+fn shared_key_debug_is_redacted() {
+  let key = SharedKey::from([0xAB; SharedKey::SIZE]);
+  assert_eq!(format!("{key:?}"), "SharedKey { .. }");
+}
+
+fn test_avalanche_effect(ss: &SharedKey, ss1: &SharedKey) {
+  let score = ss
+    .as_bytes()
+    .iter()
+    .zip(ss1.as_bytes())
+    .filter(|(s1, s2)| s1 == s2)
+    .count();
+  const HIGHEST_SCORE: usize = 2;
+  assert!(
+    score < HIGHEST_SCORE,
+    "{score} bytes of wrong SharedKey were equal to true SharedKey. Threshold: {HIGHEST_SCORE}."
+  )
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "envelope")]
+
+mod common;
+
+use common::counter;
+use rmce::envelope::*;
+use rmce::{generate_keypair_with_entropy_provider, Error, ShareableSecret};
+
+#[test]
+fn envelope_opens_for_every_recipient() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let (pks, sks): (Vec<_>, Vec<_>) = keypair_randombytes[..4]
+    .iter()
+    .map(|k| {
+      generate_keypair_with_entropy_provider(|data| {
+        data.copy_from_slice(k);
+      })
+    })
+    .unzip();
+
+  let envelope = seal_with_entropy_provider(&pks[..3], b"archive", b"job-7", counter(0)).unwrap();
+  assert_eq!(envelope.len(), 7 + 3 * STANZA_LEN + 7 + 16);
+  for sk in &sks[..3] {
+    assert_eq!(open(sk, &envelope, b"job-7").unwrap(), b"archive");
+  }
+  assert!(matches!(
+    open(&sks[3], &envelope, b"job-7"),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    open(&sks[0], &envelope, b"job-8"),
+    Err(Error::DecryptionFailed)
+  ));
+
+  /* drop the last stanza */
+  let mut removed = envelope[..7 + 2 * STANZA_LEN].to_vec();
+  removed[6] = 2;
+  removed.extend_from_slice(&envelope[7 + 3 * STANZA_LEN..]);
+  /* swap the first two stanzas */
+  let mut swapped = envelope.clone();
+  let (first, second) = swapped[7..7 + 2 * STANZA_LEN].split_at_mut(STANZA_LEN);
+  first.swap_with_slice(second);
+  /* replace the last stanza with one for another key */
+  let mut replaced = envelope.clone();
+  let (enc, _) = pks[3].encapsulate_with_entropy_provider(counter(9));
+  replaced[7 + 2 * STANZA_LEN..7 + 2 * STANZA_LEN + ShareableSecret::SIZE]
+    .copy_from_slice(enc.as_bytes());
+  for tampered in [removed, swapped, replaced] {
+    for sk in &sks[..2] {
+      assert!(matches!(
+        open(sk, &tampered, b"job-7"),
+        Err(Error::DecryptionFailed)
+      ));
+    }
+  }
+
+  let mut count = envelope.clone();
+  count[6] = 4;
+  assert!(matches!(
+    open(&sks[0], &count, b"job-7"),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    seal_with_entropy_provider(&[], b"archive", b"", counter(0)),
+    Err(Error::InvalidRecipientCount { got: 0, .. })
+  ));
+}
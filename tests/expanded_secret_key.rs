@@ -0,0 +1,32 @@
+use rmce::*;
+
+#[test]
+fn expanded_secret_key_opens_same_secret() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let results = include!("resources/results.in");
+  for ((k, e), r) in keypair_randombytes
+    .iter()
+    .zip(encrypt_randombytes)
+    .zip(results)
+    .take(3)
+  {
+    let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+      data.copy_from_slice(k);
+    });
+    let esk = sk.expand();
+
+    let (ct, ss) = pk.encapsulate_with_entropy_provider(|data| {
+      data.copy_from_slice(&e);
+    });
+
+    assert_eq!(ct.decapsulate_expanded(&esk), ss);
+    assert_eq!(ss.as_bytes(), &r);
+
+    let mut ct_altered: [u8; ShareableSecret::SIZE] = ct.into();
+    ct_altered[2] = ct_altered[2].wrapping_add(1);
+    let ct_altered: ShareableSecret = ct_altered.into();
+
+    assert_eq!(ct_altered.decapsulate_expanded(&esk), ct_altered.decapsulate(&sk));
+  }
+}
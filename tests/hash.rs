@@ -0,0 +1,144 @@
+#![cfg(feature = "hash")]
+
+use rmce::hash::*;
+
+fn unhex(s: &str) -> Vec<u8> {
+  (0..s.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+    .collect()
+}
+
+fn xof(reader: &mut XofReader, len: usize) -> Vec<u8> {
+  let mut out = vec![0u8; len];
+  reader.read(&mut out);
+  out
+}
+
+// FIPS 202 examples
+
+#[test]
+fn sha3() {
+  let mut h = Sha3_256::new();
+  h.update(b"abc");
+  assert_eq!(
+    h.finalize().to_vec(),
+    unhex("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")
+  );
+  assert_eq!(
+    Sha3_256::new().finalize().to_vec(),
+    unhex("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a")
+  );
+
+  let mut h = Sha3_512::new();
+  h.update(b"abc");
+  assert_eq!(
+    h.finalize().to_vec(),
+    unhex("b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0")
+  );
+
+  // 1600-bit message of 0xa3, fed in uneven pieces
+  let message = [0xa3u8; 200];
+  let mut h = Sha3_512::new();
+  for chunk in message.chunks(7) {
+    h.update(chunk);
+  }
+  assert_eq!(
+    h.finalize().to_vec(),
+    unhex("e76dfad22084a8b1467fcf2ffa58361bec7628edf5f3fdc0e4805dc48caeeca81b7c13c30adf52a3659584739a2df46be589c51ca1a4a8416df6545a1ce8ba00")
+  );
+}
+
+#[test]
+fn shake() {
+  assert_eq!(
+    xof(&mut Shake128::new().finalize_xof(), 32),
+    unhex("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26")
+  );
+  assert_eq!(
+    xof(&mut Shake256::new().finalize_xof(), 64),
+    unhex("46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be")
+  );
+
+  let message = [0xa3u8; 200];
+  let mut h = Shake256::new();
+  h.update(&message[..135]);
+  h.update(&message[135..]);
+  let mut reader = h.finalize_xof();
+  let mut out = xof(&mut reader, 10);
+  out.extend(xof(&mut reader, 54));
+  assert_eq!(
+    out,
+    unhex("cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d2d700caae7396ece96604440577da4f3aa22aeb8857f961c4cd8e06f0ae6610b")
+  );
+}
+
+// NIST SP 800-185 samples
+
+#[test]
+fn cshake() {
+  let data = [0u8, 1, 2, 3];
+  let mut h = CShake128::new(b"", b"Email Signature");
+  h.update(&data);
+  assert_eq!(
+    xof(&mut h.finalize_xof(), 32),
+    unhex("c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5")
+  );
+
+  let mut h = CShake256::new(b"", b"Email Signature");
+  h.update(&data);
+  assert_eq!(
+    xof(&mut h.finalize_xof(), 64),
+    unhex("d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c")
+  );
+}
+
+#[test]
+fn kmac() {
+  let key: Vec<u8> = (0x40..0x60).collect();
+  let data = [0u8, 1, 2, 3];
+
+  let mut h = Kmac128::new(&key, b"");
+  h.update(&data);
+  let mut out = [0u8; 32];
+  h.finalize_into(&mut out);
+  assert_eq!(
+    out.to_vec(),
+    unhex("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e")
+  );
+
+  let mut h = Kmac256::new(&key, b"My Tagged Application");
+  h.update(&data);
+  let mut out = [0u8; 64];
+  h.finalize_into(&mut out);
+  assert_eq!(
+    out.to_vec(),
+    unhex("20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd")
+  );
+
+  let mut h = Kmac256::new(&key, b"My Tagged Application");
+  h.update(&data);
+  assert_eq!(
+    xof(&mut h.finalize_xof(), 64),
+    unhex("1755133f1534752aad0748f2c706fb5c784512cab835cd15676b16c0c6647fa96faa7af634a0bf8ff6df39374fa00fad9a39e322a7c92065a64eb1fb0801eb2b")
+  );
+}
+
+#[test]
+fn digest_traits() {
+  use rmce::hash::digest::{Digest, ExtendableOutput, Update};
+
+  assert_eq!(
+    <Sha3_256 as Digest>::digest(b"abc").to_vec(),
+    unhex("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")
+  );
+
+  let mut out = [0u8; 32];
+  let mut h = Shake128::default();
+  Update::update(&mut h, b"");
+  ExtendableOutput::finalize_xof_into(h, &mut out);
+  assert_eq!(
+    out.to_vec(),
+    unhex("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26")
+  );
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "hpke")]
+
+use rmce::hpke::*;
+use rmce::{generate_keypair_with_entropy_provider, Error};
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/* vectors generated from the deterministic keypair and encryption randomness
+of the first KAT entry */
+#[test]
+fn hpke_deterministic_vectors() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[0]);
+  });
+  let psk = [0x42u8; 32];
+  let vectors = [
+    (
+      Aead::Aes128Gcm,
+      Mode::Base,
+      [
+        "cb3810bfab65e87a3d9326f47e41f1e35bd2858e3c59784dba",
+        "b6f94789c0cfcb3ab05a2912eaad3928381df2fedcd2bc4e39",
+      ],
+      "0e2d83e5ea73b88ee882c9cc1f7db0b658f5b68c9bea392538577cbed99f4f30",
+    ),
+    (
+      Aead::ChaCha20Poly1305,
+      Mode::Psk {
+        psk: &psk,
+        psk_id: b"psk-1",
+      },
+      [
+        "db85e12b7cce53b4263f23c9dbd7ddcd308b3fe4c227f47ce8",
+        "9fec0230a52f2e89ec4f25ef2a6e033f8d2cba1782d1574650",
+      ],
+      "73dd51ed02d009acc0ef4a6f5a3b71971250f6fba05ee0ca6722dcb969efefc9",
+    ),
+  ];
+  for (aead, mode, cts, exported) in vectors {
+    let (enc, mut sender) = setup_sender_with_entropy_provider(&pk, aead, mode, b"info", |data| {
+      data.copy_from_slice(&encrypt_randombytes[0]);
+    })
+    .unwrap();
+    let mut receiver = setup_receiver(&enc, &sk, aead, mode, b"info").unwrap();
+
+    for ct in cts {
+      let sealed = sender.seal(b"aad", b"plaintext").unwrap();
+      assert_eq!(hex(&sealed), ct);
+      assert_eq!(receiver.open(b"aad", &sealed).unwrap(), b"plaintext");
+    }
+    assert_eq!(hex(sender.export(b"ctx", 32).unwrap().as_bytes()), exported);
+    assert_eq!(
+      receiver.export(b"ctx", 32).unwrap(),
+      sender.export(b"ctx", 32).unwrap()
+    );
+  }
+}
+
+#[test]
+fn hpke_single_shot_and_failures() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[1]);
+  });
+  let psk = [7u8; 32];
+  let mode = Mode::Psk {
+    psk: &psk,
+    psk_id: b"id",
+  };
+
+  let (enc, ct) = seal_with_entropy_provider(
+    &pk,
+    Aead::Aes256Gcm,
+    mode,
+    b"info",
+    b"aad",
+    b"message",
+    |data| {
+      data.copy_from_slice(&encrypt_randombytes[1]);
+    },
+  )
+  .unwrap();
+  let pt = open(&enc, &sk, Aead::Aes256Gcm, mode, b"info", b"aad", &ct).unwrap();
+  assert_eq!(pt, b"message");
+
+  let wrong_psk = [8u8; 32];
+  let wrong_mode = Mode::Psk {
+    psk: &wrong_psk,
+    psk_id: b"id",
+  };
+  for (aead, mode, info, aad) in [
+    (Aead::Aes256Gcm, wrong_mode, &b"info"[..], &b"aad"[..]),
+    (Aead::Aes256Gcm, Mode::Base, b"info", b"aad"),
+    (Aead::Aes256Gcm, mode, b"other", b"aad"),
+    (Aead::Aes256Gcm, mode, b"info", b"other"),
+    (Aead::ChaCha20Poly1305, mode, b"info", b"aad"),
+  ] {
+    assert!(matches!(
+      open(&enc, &sk, aead, mode, info, aad, &ct),
+      Err(Error::DecryptionFailed)
+    ));
+  }
+
+  let mut tampered = ct.clone();
+  tampered[0] ^= 1;
+  assert!(matches!(
+    open(&enc, &sk, Aead::Aes256Gcm, mode, b"info", b"aad", &tampered),
+    Err(Error::DecryptionFailed)
+  ));
+
+  let empty_id = Mode::Psk {
+    psk: &psk,
+    psk_id: b"",
+  };
+  assert!(matches!(
+    open(&enc, &sk, Aead::Aes256Gcm, empty_id, b"info", b"aad", &ct),
+    Err(Error::InvalidPsk)
+  ));
+}
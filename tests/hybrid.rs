@@ -0,0 +1,92 @@
+#![cfg(feature = "hybrid")]
+
+mod common;
+
+use common::counter;
+use rmce::hybrid::*;
+
+#[test]
+fn hybrid_round_trip() {
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, key) = pk.encapsulate_with_entropy_provider(counter(100));
+
+  assert_eq!(ct.decapsulate(&sk), key);
+  assert_eq!(ct.try_decapsulate(&sk).unwrap(), key);
+
+  let (ct1, key1) = pk.encapsulate_with_entropy_provider(counter(100));
+  assert_eq!(ct, ct1);
+  assert_eq!(key, key1);
+
+  let pk1 = PublicKey::try_from(&pk.to_bytes()[..]).unwrap();
+  let sk1 = SecretKey::try_from(&sk.to_bytes()[..]).unwrap();
+  let ct1 = ShareableSecret::try_from(&ct.to_bytes()[..]).unwrap();
+  assert_eq!(pk1, pk);
+  assert_eq!(sk1, sk);
+  assert_eq!(ct1.decapsulate(&sk1), key);
+  assert_eq!(sk.public_key().unwrap(), pk);
+  assert_eq!(
+    SecretKey::new(sk.mceliece().clone(), sk.x25519().clone()).unwrap(),
+    sk
+  );
+
+  assert!(matches!(
+    ShareableSecret::try_from(&ct.to_bytes()[1..]),
+    Err(rmce::Error::InvalidLength { .. })
+  ));
+
+  /* the stored digest of the McEliece public key is checked */
+  let mut forged = sk.to_bytes();
+  forged[SecretKey::SIZE - 1] ^= 1;
+  assert!(matches!(
+    SecretKey::try_from(&forged[..]),
+    Err(rmce::Error::InvalidSecretKey)
+  ));
+}
+
+#[test]
+fn hybrid_key_depends_on_both_parts() {
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, key) = pk.encapsulate_with_entropy_provider(counter(100));
+  let bytes = ct.to_bytes();
+
+  let mut tampered = bytes;
+  tampered[0] ^= 1;
+  let tampered = ShareableSecret::try_from(&tampered[..]).unwrap();
+  assert_ne!(tampered.decapsulate(&sk), key);
+
+  let mut tampered = bytes;
+  tampered[ShareableSecret::SIZE - 1] ^= 1;
+  let tampered = ShareableSecret::try_from(&tampered[..]).unwrap();
+  assert_ne!(tampered.decapsulate(&sk), key);
+
+  let mut other_x25519 = sk.to_bytes();
+  other_x25519[rmce::SecretKey::SIZE] ^= 0x40;
+  let other_x25519 = SecretKey::try_from(&other_x25519[..]).unwrap();
+  assert_ne!(ct.decapsulate(&other_x25519), key);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn hybrid_serde_round_trip() {
+  use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, _) = pk.encapsulate_with_entropy_provider(counter(100));
+
+  let json = serde_json::to_string(&pk).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(pk.to_bytes())));
+  assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pk);
+  let json = serde_json::to_string(&sk).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(&sk.to_bytes()[..])));
+  assert_eq!(serde_json::from_str::<SecretKey>(&json).unwrap(), sk);
+  let json = serde_json::to_string(&ct).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(ct.to_bytes())));
+  assert_eq!(serde_json::from_str::<ShareableSecret>(&json).unwrap(), ct);
+
+  let mut cbor = Vec::new();
+  ciborium::into_writer(&ct, &mut cbor).unwrap();
+  assert_eq!(ciborium::from_reader::<ShareableSecret, _>(&cbor[..]).unwrap(), ct);
+
+  let short = format!("\"{}\"", BASE64.encode(&ct.to_bytes()[1..]));
+  assert!(serde_json::from_str::<ShareableSecret>(&short).is_err());
+}
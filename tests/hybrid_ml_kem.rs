@@ -0,0 +1,81 @@
+#![cfg(feature = "ml-kem")]
+
+mod common;
+
+use common::counter;
+use rmce::hybrid_ml_kem::{ml_kem_1024, ml_kem_768};
+
+#[test]
+fn ml_kem_768_round_trip() {
+  use ml_kem_768::*;
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, key) = pk.encapsulate_with_entropy_provider(counter(100));
+
+  assert_eq!(ct.decapsulate(&sk), key);
+  assert_eq!(ct.try_decapsulate(&sk).unwrap(), key);
+
+  let pk1 = PublicKey::try_from(&pk.to_bytes()[..]).unwrap();
+  let sk1 = SecretKey::try_from(&sk.to_bytes()[..]).unwrap();
+  let ct1 = ShareableSecret::try_from(&ct.to_bytes()[..]).unwrap();
+  assert_eq!(pk1, pk);
+  assert_eq!(sk1, sk);
+  assert_eq!(ct1.decapsulate(&sk1), key);
+  assert_eq!(sk.public_key().unwrap(), pk);
+
+  let mut tampered = ct.to_bytes();
+  tampered[ShareableSecret::SIZE - 1] ^= 1;
+  let tampered = ShareableSecret::try_from(&tampered[..]).unwrap();
+  assert_ne!(tampered.decapsulate(&sk), key);
+
+  let mut tampered = ct.to_bytes();
+  tampered[0] ^= 1;
+  let tampered = ShareableSecret::try_from(&tampered[..]).unwrap();
+  assert_ne!(tampered.decapsulate(&sk), key);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ml_kem_serde_round_trip() {
+  use ml_kem_768::*;
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, _) = pk.encapsulate_with_entropy_provider(counter(100));
+
+  let json = serde_json::to_string(&pk).unwrap();
+  assert!(json.starts_with('"'));
+  assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pk);
+  let json = serde_json::to_string(&sk).unwrap();
+  assert_eq!(serde_json::from_str::<SecretKey>(&json).unwrap(), sk);
+  let json = serde_json::to_string(&ct).unwrap();
+  assert_eq!(serde_json::from_str::<ShareableSecret>(&json).unwrap(), ct);
+  assert!(serde_json::from_str::<ml_kem_1024::ShareableSecret>(&json).is_err());
+}
+
+#[test]
+fn ml_kem_1024_round_trip() {
+  use ml_kem_1024::*;
+  let (pk, sk) = generate_keypair_with_entropy_provider(counter(0));
+  let (ct, key) = pk.encapsulate_with_entropy_provider(counter(100));
+
+  assert_eq!(ct.decapsulate(&sk), key);
+  assert_eq!(
+    ShareableSecret::SIZE,
+    rmce::ShareableSecret::SIZE + ct.ml_kem().len()
+  );
+
+  let sk1 = SecretKey::try_from(&sk.to_bytes()[..]).unwrap();
+  let ct1 = ShareableSecret::try_from(&ct.to_bytes()[..]).unwrap();
+  assert_eq!(ct1.decapsulate(&sk1), key);
+
+  let mut forged = sk.to_bytes();
+  forged[SecretKey::SIZE - 1] ^= 1;
+  assert!(matches!(
+    SecretKey::try_from(&forged[..]),
+    Err(rmce::Error::InvalidSecretKey)
+  ));
+
+  let (ct768, key768) = ml_kem_768::generate_keypair_with_entropy_provider(counter(0))
+    .0
+    .encapsulate_with_entropy_provider(counter(100));
+  assert_ne!(key768, key);
+  assert!(ShareableSecret::try_from(&ct768.to_bytes()[..]).is_err());
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "jose")]
+
+mod common;
+
+use common::{counter, keypair};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use rmce::jose::*;
+use rmce::{Error, ParameterSet};
+
+#[test]
+fn jwk_round_trip() {
+  let (pk, sk) = keypair(0);
+  let (other_pk, _) = keypair(1);
+
+  let json = encode_public_key(&pk);
+  assert!(json.starts_with(r#"{"kty":"AKP","alg":"MCELIECE8192128F","pub":""#));
+  assert_eq!(decode_public_key(&json).unwrap(), pk);
+  let json = encode_secret_key(&sk).unwrap();
+  assert!(json.contains(r#""priv":""#));
+  assert_eq!(decode_secret_key(&json).unwrap(), sk);
+  assert_eq!(decode_public_key(&json).unwrap(), pk);
+  assert!(matches!(
+    decode_secret_key(&encode_public_key(&pk)),
+    Err(Error::InvalidEncoding)
+  ));
+
+  let jwk = Jwk::from_secret_key(&sk).unwrap();
+  let mut other_set = jwk.clone();
+  other_set.alg = ParameterSet::Mceliece6960119f.jose_alg().to_owned();
+  assert!(matches!(
+    other_set.public_key(),
+    Err(Error::AlgorithmMismatch)
+  ));
+  let mut other_kty = jwk.clone();
+  other_kty.kty = "OKP".to_owned();
+  assert!(matches!(
+    other_kty.secret_key(),
+    Err(Error::InvalidEncoding)
+  ));
+  let mut mismatched = jwk.clone();
+  mismatched.public = Jwk::from_public_key(&other_pk).public.clone();
+  assert!(matches!(
+    mismatched.secret_key(),
+    Err(Error::KeyPairMismatch)
+  ));
+  let mut padded = jwk.clone();
+  padded.public.push('=');
+  assert!(matches!(padded.public_key(), Err(Error::InvalidEncoding)));
+
+  assert_eq!(
+    ParameterSet::from_jose_alg("MCELIECE8192128F"),
+    Some(ParameterSet::Mceliece8192128f)
+  );
+  /* the thumbprint only covers the public members */
+  assert_eq!(jwk.thumbprint(), Jwk::from_public_key(&pk).thumbprint());
+  assert_ne!(
+    jwk.thumbprint(),
+    Jwk::from_public_key(&other_pk).thumbprint()
+  );
+}
+
+#[test]
+fn jwe_round_trip() {
+  let (pk, sk) = keypair(2);
+  let (_, other_sk) = keypair(3);
+  let plaintext = b"Live long and prosper.";
+
+  let jwe = encrypt_with_entropy_provider(&pk, plaintext, Some("key-2"), counter(0));
+  let parts: Vec<&str> = jwe.split('.').collect();
+  assert_eq!(parts.len(), 5);
+  assert_eq!(
+    BASE64URL.decode(parts[0]).unwrap(),
+    br#"{"alg":"MCELIECE8192128F","enc":"A256GCM","kid":"key-2"}"#
+  );
+  assert_eq!(
+    BASE64URL.decode(parts[1]).unwrap().len(),
+    rmce::ShareableSecret::SIZE
+  );
+  assert_eq!(decrypt(&sk, &jwe).unwrap(), plaintext);
+  assert!(matches!(
+    decrypt(&other_sk, &jwe),
+    Err(Error::DecryptionFailed)
+  ));
+
+  let with_header = |header: &str| {
+    let mut parts = parts.clone();
+    let header = BASE64URL.encode(header);
+    parts[0] = &header;
+    parts.join(".")
+  };
+  /* the protected header is authenticated */
+  assert!(matches!(
+    decrypt(
+      &sk,
+      &with_header(r#"{"alg":"MCELIECE8192128F","enc":"A256GCM"}"#)
+    ),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    decrypt(
+      &sk,
+      &with_header(r#"{"alg":"MCELIECE8192128F","enc":"A128GCM"}"#)
+    ),
+    Err(Error::AlgorithmMismatch)
+  ));
+  assert!(matches!(
+    decrypt(
+      &sk,
+      &with_header(r#"{"alg":"MCELIECE8192128F","enc":"A256GCM","crit":["exp"],"exp":0}"#)
+    ),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    decrypt(&sk, &parts[..4].join(".")),
+    Err(Error::InvalidHeader)
+  ));
+  let mut tag = BASE64URL.decode(parts[4]).unwrap();
+  tag[0] ^= 1;
+  let mut tampered = parts.clone();
+  let tag = BASE64URL.encode(tag);
+  tampered[4] = &tag;
+  assert!(matches!(
+    decrypt(&sk, &tampered.join(".")),
+    Err(Error::DecryptionFailed)
+  ));
+}
+
+/* the Concat KDF and the content decryption redone with OpenSSL */
+#[cfg(feature = "openssl")]
+#[test]
+fn jwe_matches_openssl() {
+  use openssl::{sha::Sha256, symm};
+
+  let (pk, sk) = keypair(4);
+  let plaintext = b"The true sign of intelligence is not knowledge but imagination.";
+  let jwe = encrypt(&pk, plaintext, None);
+  let parts: Vec<Vec<u8>> = jwe
+    .split('.')
+    .map(|part| BASE64URL.decode(part).unwrap())
+    .collect();
+
+  let enc = rmce::ShareableSecret::try_from(&parts[1][..]).unwrap();
+  let key = enc.decapsulate(&sk);
+  let mut hash = Sha256::new();
+  hash.update(&1u32.to_be_bytes());
+  hash.update(key.as_bytes());
+  hash.update(&7u32.to_be_bytes());
+  hash.update(b"A256GCM");
+  hash.update(&[0u8; 8]);
+  hash.update(&256u32.to_be_bytes());
+  let content_key = hash.finish();
+
+  let aad = jwe.split('.').next().unwrap();
+  let decrypted = symm::decrypt_aead(
+    symm::Cipher::aes_256_gcm(),
+    &content_key,
+    Some(&parts[2]),
+    aad.as_bytes(),
+    &parts[3],
+    &parts[4],
+  )
+  .unwrap();
+  assert_eq!(decrypted, plaintext);
+}
@@ -0,0 +1,37 @@
+use rmce::*;
+
+#[test]
+fn derive_separates_labels_and_contexts() {
+  let plain = PlainSecret::from(vec![3u8; 32]);
+
+  let a = plain.derive(b"encryption", b"session 1", 32);
+  let b = plain.derive(b"authentication", b"session 1", 32);
+  let c = plain.derive(b"encryption", b"session 2", 32);
+
+  assert_ne!(a, b);
+  assert_ne!(a, c);
+  assert_eq!(a, plain.derive(b"encryption", b"session 1", 32));
+  assert_ne!(
+    a.as_bytes(),
+    &plain.derive(b"encryption", b"session 1", 64).as_bytes()[..32]
+  );
+
+  let key = SharedKey::from([3u8; SharedKey::SIZE]);
+  assert_eq!(key.derive(b"encryption", b"session 1", 32), a);
+}
+
+#[cfg(feature = "hkdf")]
+#[test]
+fn derive_hkdf() {
+  let plain = PlainSecret::from(vec![3u8; 32]);
+
+  let a = plain.derive_hkdf(b"encryption", b"", 32).unwrap();
+  let b = plain.derive_hkdf(b"encryptio", b"n", 32).unwrap();
+  assert_ne!(a, b);
+  assert_ne!(a, plain.derive(b"encryption", b"", 32));
+
+  assert!(matches!(
+    plain.derive_hkdf(b"encryption", b"", 255 * 32 + 1),
+    Err(Error::OutputTooLong { .. })
+  ));
+}
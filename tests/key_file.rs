@@ -0,0 +1,116 @@
+mod common;
+
+use common::keypair;
+use rmce::key_file::{KeyFile, KeyType, MAX_KEY_ID_LEN};
+use rmce::{Error, ParameterSet, PublicKey, SecretKey};
+
+#[test]
+fn key_file_round_trip() {
+  let (pk, sk) = keypair(0);
+
+  let bytes = pk.to_file_bytes(Some(b"alice")).unwrap();
+  assert_eq!(&bytes[..14], b"RMCK\x01\x00\x0a\x01\x05alice");
+  assert_eq!(&bytes[14..18], &(PublicKey::SIZE as u32).to_be_bytes());
+  assert_eq!(&bytes[18..18 + PublicKey::SIZE], pk.as_bytes());
+  assert_eq!(bytes.len(), 18 + PublicKey::SIZE + 4);
+  assert_eq!(PublicKey::from_file_bytes(&bytes).unwrap(), pk);
+  let file = KeyFile::parse(&bytes).unwrap();
+  assert_eq!(file.parameter_set, ParameterSet::Mceliece8192128f);
+  assert_eq!(file.key_type, KeyType::Public);
+  assert_eq!(file.key_id, Some(&b"alice"[..]));
+
+  let bytes = sk.to_file_bytes(None).unwrap();
+  assert_eq!(&bytes[..9], b"RMCK\x01\x00\x0a\x02\x00");
+  assert_eq!(KeyFile::parse(&bytes).unwrap().key_id, None);
+  assert_eq!(SecretKey::from_file_bytes(&bytes).unwrap(), sk);
+  /* an empty key ID is no key ID */
+  let empty = sk.to_file_bytes(Some(b"")).unwrap();
+  assert_eq!(*empty, *bytes);
+
+  let long = [b'x'; MAX_KEY_ID_LEN];
+  let bytes = pk.to_file_bytes(Some(&long)).unwrap();
+  assert_eq!(KeyFile::parse(&bytes).unwrap().key_id, Some(&long[..]));
+  assert!(matches!(
+    pk.to_file_bytes(Some(&[b'x'; MAX_KEY_ID_LEN + 1])),
+    Err(Error::InvalidKeyIdLength { got: 256, max: 255 })
+  ));
+}
+
+#[test]
+fn key_file_rejects_mismatches() {
+  let (pk, sk) = keypair(1);
+  let pk_bytes = pk.to_file_bytes(None).unwrap();
+  let sk_bytes = sk.to_file_bytes(Some(b"key-1")).unwrap();
+
+  assert!(matches!(
+    PublicKey::from_file_bytes(&sk_bytes),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    SecretKey::from_file_bytes(&pk_bytes),
+    Err(Error::InvalidHeader)
+  ));
+
+  /* a key of another parameter set parses, but does not load */
+  let other = KeyFile {
+    parameter_set: ParameterSet::Mceliece6960119f,
+    key_type: KeyType::Public,
+    key_id: None,
+    key: &[0u8; 1047319],
+  }
+  .to_bytes()
+  .unwrap();
+  assert_eq!(
+    KeyFile::parse(&other).unwrap().parameter_set,
+    ParameterSet::Mceliece6960119f
+  );
+  assert!(matches!(
+    PublicKey::from_file_bytes(&other),
+    Err(Error::AlgorithmMismatch)
+  ));
+  let mut unknown = pk_bytes.clone();
+  unknown[5..7].copy_from_slice(&[0xff, 0xff]);
+  assert!(matches!(
+    KeyFile::parse(&unknown),
+    Err(Error::AlgorithmMismatch)
+  ));
+
+  let mut corrupted = pk_bytes.clone();
+  corrupted[100] ^= 1;
+  assert!(matches!(
+    PublicKey::from_file_bytes(&corrupted),
+    Err(Error::InvalidEncoding)
+  ));
+  let mut version = pk_bytes.clone();
+  version[4] = 2;
+  assert!(matches!(
+    PublicKey::from_file_bytes(&version),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    PublicKey::from_file_bytes(&pk_bytes[..pk_bytes.len() - 1]),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    PublicKey::from_file_bytes(pk.as_bytes()),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    PublicKey::from_file_bytes(b"RMCK"),
+    Err(Error::InvalidLength { got: 4, .. })
+  ));
+
+  /* a well-formed file of a key of the wrong length */
+  let short = KeyFile {
+    parameter_set: ParameterSet::Mceliece8192128f,
+    key_type: KeyType::Secret,
+    key_id: None,
+    key: &sk.as_bytes()[1..],
+  }
+  .to_bytes()
+  .unwrap();
+  assert!(matches!(
+    SecretKey::from_file_bytes(&short),
+    Err(Error::InvalidLength { .. })
+  ));
+}
@@ -0,0 +1,62 @@
+use rmce::*;
+
+#[test]
+fn valid_keys_accepted() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[0]);
+  });
+
+  sk.validate().unwrap();
+  validate_pair(&pk, &sk).unwrap();
+}
+
+#[test]
+fn corrupted_secret_key_rejected() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[1]);
+  });
+  let (pk_other, _) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[2]);
+  });
+
+  // pivots, Goppa polynomial coefficient
+  for offset in [33, 40 + 17] {
+    let mut bytes = sk.as_bytes().to_vec();
+    bytes[offset] ^= 0x01;
+    let corrupted = SecretKey::try_from(bytes).unwrap();
+    assert!(matches!(corrupted.validate(), Err(Error::InvalidSecretKey)));
+  }
+
+  // control bits, not every flip is caught without the public key
+  for offset in [40 + 256, 40 + 256 + 100] {
+    let mut bytes = sk.as_bytes().to_vec();
+    bytes[offset] ^= 0x01;
+    let corrupted = SecretKey::try_from(bytes).unwrap();
+    assert!(validate_pair(&pk, &corrupted).is_err());
+  }
+
+  // pivots, still 32 of them: the field cannot be checked against the key
+  let mut bytes = sk.as_bytes().to_vec();
+  let pivots = u64::from_le_bytes(bytes[32..40].try_into().unwrap());
+  let set = pivots.trailing_zeros();
+  let clear = (!pivots).trailing_zeros();
+  let moved = pivots ^ (1 << set) ^ (1 << clear);
+  bytes[32..40].copy_from_slice(&moved.to_le_bytes());
+  let corrupted = SecretKey::try_from(bytes).unwrap();
+  corrupted.validate().unwrap();
+  validate_pair(&pk, &corrupted).unwrap();
+
+  // coefficient outside of the field
+  let mut bytes = sk.as_bytes().to_vec();
+  bytes[41] |= 0x80;
+  let corrupted = SecretKey::try_from(bytes).unwrap();
+  assert!(matches!(corrupted.validate(), Err(Error::InvalidSecretKey)));
+
+  assert!(matches!(
+    validate_pair(&pk_other, &sk),
+    Err(Error::KeyPairMismatch)
+  ));
+  validate_pair(&pk, &sk).unwrap();
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "passphrase")]
+
+mod common;
+
+use common::{counter, keypair};
+use rmce::key_file::{KeyFile, KeyType};
+use rmce::passphrase::{KdfParams, ENCRYPTED_LEN};
+use rmce::{Error, ParameterSet, SecretKey};
+
+const CHEAP: KdfParams = KdfParams {
+  memory_kib: 64,
+  iterations: 1,
+  parallelism: 2,
+};
+
+#[test]
+fn passphrase_round_trip() {
+  let (_, sk) = keypair(0);
+  let passphrase = b"correct horse battery staple";
+
+  let encrypted = sk
+    .encrypt_with_passphrase_with_entropy_provider(
+      passphrase,
+      CHEAP,
+      Some(b"laptop"),
+      counter(0),
+    )
+    .unwrap();
+  let file = KeyFile::parse(&encrypted).unwrap();
+  assert_eq!(file.parameter_set, ParameterSet::Mceliece8192128f);
+  assert_eq!(file.key_type, KeyType::EncryptedSecret);
+  assert_eq!(file.key_id, Some(&b"laptop"[..]));
+  assert_eq!(file.key.len(), ENCRYPTED_LEN);
+  assert_eq!(
+    &file.key[..12],
+    b"\x00\x00\x00\x40\x00\x00\x00\x01\x00\x00\x00\x02"
+  );
+  /* salt and nonce */
+  assert_eq!(file.key[12..52], (0..40).collect::<Vec<u8>>());
+  assert_eq!(
+    SecretKey::decrypt_with_passphrase(&encrypted, passphrase).unwrap(),
+    sk
+  );
+  assert!(matches!(
+    SecretKey::decrypt_with_passphrase(&encrypted, b"Correct horse battery staple"),
+    Err(Error::DecryptionFailed)
+  ));
+
+  /* a new salt and nonce give another encryption */
+  let other = sk
+    .encrypt_with_passphrase_with_entropy_provider(passphrase, CHEAP, None, counter(1))
+    .unwrap();
+  assert_ne!(KeyFile::parse(&other).unwrap().key, file.key);
+  assert_eq!(
+    SecretKey::decrypt_with_passphrase(&other, passphrase).unwrap(),
+    sk
+  );
+
+  assert_eq!(
+    KdfParams::default(),
+    KdfParams {
+      memory_kib: 65536,
+      iterations: 3,
+      parallelism: 4,
+    }
+  );
+}
+
+#[test]
+fn passphrase_rejects_tampering() {
+  let (_, sk) = keypair(1);
+  let passphrase = b"hunter2";
+  let encrypted = sk
+    .encrypt_with_passphrase_with_entropy_provider(passphrase, CHEAP, None, counter(2))
+    .unwrap();
+  let decrypt = |encrypted: &[u8]| SecretKey::decrypt_with_passphrase(encrypted, passphrase);
+  let file = KeyFile::parse(&encrypted).unwrap();
+  /* rewrites the key with a valid checksum, so only the encryption catches it */
+  let altered = |f: &dyn Fn(&mut Vec<u8>)| {
+    let mut key = file.key.to_vec();
+    f(&mut key);
+    KeyFile { key: &key, ..file }.to_bytes().unwrap()
+  };
+
+  /* the costs, salt, nonce and the parameter set are authenticated */
+  let iterations = altered(&|key| key[7] = 2);
+  assert!(matches!(decrypt(&iterations), Err(Error::DecryptionFailed)));
+  let nonce = altered(&|key| key[51] ^= 1);
+  assert!(matches!(decrypt(&nonce), Err(Error::DecryptionFailed)));
+  let sealed = altered(&|key| key[1000] ^= 1);
+  assert!(matches!(decrypt(&sealed), Err(Error::DecryptionFailed)));
+  let other_set = KeyFile {
+    parameter_set: ParameterSet::Mceliece348864,
+    ..file
+  }
+  .to_bytes()
+  .unwrap();
+  assert!(matches!(decrypt(&other_set), Err(Error::AlgorithmMismatch)));
+
+  let no_lanes = altered(&|key| key[11] = 0);
+  assert!(matches!(decrypt(&no_lanes), Err(Error::InvalidHeader)));
+  /* costs above the limits are refused before running Argon2 on them */
+  let huge_memory = altered(&|key| key[0] = 0xff);
+  assert!(matches!(decrypt(&huge_memory), Err(Error::InvalidKdfParams)));
+  let many_iterations = altered(&|key| key[7] = 9);
+  assert!(matches!(decrypt(&many_iterations), Err(Error::InvalidKdfParams)));
+  let limits = KdfParams {
+    iterations: 0,
+    ..CHEAP
+  };
+  assert!(matches!(
+    SecretKey::decrypt_with_passphrase_and_limits(&encrypted, passphrase, limits),
+    Err(Error::InvalidKdfParams)
+  ));
+  assert_eq!(
+    SecretKey::decrypt_with_passphrase_and_limits(&encrypted, passphrase, CHEAP).unwrap(),
+    sk
+  );
+  let short = altered(&|key| {
+    key.pop();
+  });
+  assert!(matches!(decrypt(&short), Err(Error::InvalidLength { .. })));
+  let mut corrupted = encrypted.clone();
+  corrupted[20] ^= 1;
+  assert!(matches!(decrypt(&corrupted), Err(Error::InvalidEncoding)));
+  assert!(matches!(decrypt(b"RMCK"), Err(Error::InvalidLength { .. })));
+
+  /* plain and encrypted secret key files are not interchangeable */
+  let plain = sk.to_file_bytes(None).unwrap();
+  assert!(matches!(decrypt(&plain), Err(Error::InvalidHeader)));
+  assert!(matches!(
+    SecretKey::from_file_bytes(&encrypted),
+    Err(Error::InvalidHeader)
+  ));
+
+  assert!(matches!(
+    sk.encrypt_with_passphrase_with_entropy_provider(
+      passphrase,
+      KdfParams {
+        parallelism: 0,
+        ..CHEAP
+      },
+      None,
+      counter(3)
+    ),
+    Err(Error::InvalidKdfParams)
+  ));
+}
+
+#[cfg(feature = "openssl")]
+#[test]
+fn passphrase_random() {
+  let (_, sk) = keypair(2);
+  let first = sk.encrypt_with_passphrase(b"", CHEAP, None).unwrap();
+  let second = sk.encrypt_with_passphrase(b"", CHEAP, None).unwrap();
+  assert_ne!(first, second);
+  assert_eq!(SecretKey::decrypt_with_passphrase(&first, b"").unwrap(), sk);
+}
@@ -0,0 +1,13 @@
+use rmce::*;
+
+#[test]
+fn public_key_derived_from_secret_key() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  for k in keypair_randombytes.iter().take(2) {
+    let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+      data.copy_from_slice(k);
+    });
+
+    assert_eq!(sk.public_key().unwrap(), pk);
+  }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "seal")]
+
+use rmce::seal::*;
+use rmce::*;
+
+#[test]
+fn seal_open_round_trip() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[0]);
+  });
+
+  for plaintext in [&b""[..], b"attack at dawn", &[0xa5; 5000]] {
+    let blob = seal_with_entropy_provider(&pk, plaintext, b"aad", |data| {
+      data.copy_from_slice(&encrypt_randombytes[0]);
+    });
+    assert_eq!(blob.len(), plaintext.len() + SEAL_OVERHEAD);
+    assert_eq!(&blob[..5], b"RMCE\x01");
+    assert_eq!(open(&sk, &blob, b"aad").unwrap(), plaintext);
+    assert_eq!(
+      open_expanded(&sk.expand(), &blob, b"aad").unwrap(),
+      plaintext
+    );
+  }
+}
+
+#[test]
+fn open_rejects_modified_blobs() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[1]);
+  });
+  let (_, other_sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[2]);
+  });
+  let blob = seal_with_entropy_provider(&pk, b"message", b"aad", |data| {
+    data.copy_from_slice(&encrypt_randombytes[1]);
+  });
+
+  assert!(matches!(
+    open(&sk, &blob, b"other aad"),
+    Err(Error::DecryptionFailed)
+  ));
+  assert!(matches!(
+    open(&other_sk, &blob, b"aad"),
+    Err(Error::DecryptionFailed)
+  ));
+  for i in [5, SEAL_OVERHEAD - 17, blob.len() - 1] {
+    let mut tampered = blob.clone();
+    tampered[i] ^= 1;
+    assert!(matches!(
+      open(&sk, &tampered, b"aad"),
+      Err(Error::DecryptionFailed)
+    ));
+  }
+
+  let mut tampered = blob.clone();
+  tampered[4] = 2;
+  assert!(matches!(
+    open(&sk, &tampered, b"aad"),
+    Err(Error::InvalidHeader)
+  ));
+  assert!(matches!(
+    open(&sk, &blob[..SEAL_OVERHEAD - 1], b"aad"),
+    Err(Error::InvalidLength { .. })
+  ));
+}
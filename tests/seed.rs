@@ -0,0 +1,20 @@
+use rmce::*;
+
+#[test]
+fn keypair_regenerated_from_seed() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let k = &keypair_randombytes[0];
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(k);
+  });
+
+  let seed = sk.seed();
+  assert_eq!(&seed, k);
+
+  let (pk1, sk1) = generate_keypair_from_seed(seed);
+  assert_eq!(pk1, pk);
+  assert_eq!(sk1, sk);
+
+  assert_eq!(SecretKey::from_seed(seed), sk);
+  assert_eq!(PublicKey::from_seed(seed), pk);
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::{counter, keypair};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rmce::{PlainSecret, PublicKey, SecretKey, ShareableSecret, SharedKey};
+use serde::{Deserialize, Serialize};
+
+fn to_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+  let mut cbor = Vec::new();
+  ciborium::into_writer(value, &mut cbor).unwrap();
+  cbor
+}
+
+fn from_cbor<T: for<'de> Deserialize<'de>>(cbor: &[u8]) -> Result<T, String> {
+  ciborium::from_reader(cbor).map_err(|err| err.to_string())
+}
+
+#[test]
+fn serde_human_readable() {
+  let (pk, sk) = keypair(0);
+  let (enc, key) = pk.encapsulate_with_entropy_provider(counter(0));
+  let plain = key.kdf(48);
+
+  let json = serde_json::to_string(&pk).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(pk.as_bytes())));
+  assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), pk);
+  let json = serde_json::to_string(&sk).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(sk.as_bytes())));
+  assert_eq!(serde_json::from_str::<SecretKey>(&json).unwrap(), sk);
+  let json = serde_json::to_string(&enc).unwrap();
+  assert_eq!(serde_json::from_str::<ShareableSecret>(&json).unwrap(), enc);
+  let json = serde_json::to_string(&key).unwrap();
+  assert_eq!(serde_json::from_str::<SharedKey>(&json).unwrap(), key);
+  let json = serde_json::to_string(&plain).unwrap();
+  assert_eq!(json, format!("\"{}\"", BASE64.encode(plain.as_bytes())));
+  assert_eq!(serde_json::from_str::<PlainSecret>(&json).unwrap(), plain);
+
+  /* unpadded base64 and arrays of integers are accepted too */
+  let unpadded = format!(
+    "\"{}\"",
+    BASE64.encode(enc.as_bytes()).trim_end_matches('=')
+  );
+  assert_eq!(
+    serde_json::from_str::<ShareableSecret>(&unpadded).unwrap(),
+    enc
+  );
+  let array = serde_json::to_string(&enc.as_bytes()[..]).unwrap();
+  assert_eq!(
+    serde_json::from_str::<ShareableSecret>(&array).unwrap(),
+    enc
+  );
+  let array = serde_json::to_string(plain.as_bytes()).unwrap();
+  assert_eq!(serde_json::from_str::<PlainSecret>(&array).unwrap(), plain);
+
+  let short = format!("\"{}\"", BASE64.encode(&enc.as_bytes()[1..]));
+  let err = serde_json::from_str::<ShareableSecret>(&short).unwrap_err();
+  assert!(err.to_string().contains("invalid length 207"), "{err}");
+  assert!(serde_json::from_str::<SharedKey>("\"not base64!\"").is_err());
+  assert!(serde_json::from_str::<SharedKey>("[1, 2, 256]").is_err());
+}
+
+#[test]
+fn serde_binary() {
+  let (pk, sk) = keypair(1);
+  let (enc, key) = pk.encapsulate_with_entropy_provider(counter(1));
+  let plain = key.kdf(16);
+
+  /* CBOR byte strings: major type 2 */
+  let cbor = to_cbor(&pk);
+  assert_eq!(cbor[0], 0x5a);
+  assert_eq!(&cbor[5..], pk.as_bytes());
+  assert_eq!(from_cbor::<PublicKey>(&cbor).unwrap(), pk);
+  assert_eq!(from_cbor::<SecretKey>(&to_cbor(&sk)).unwrap(), sk);
+  assert_eq!(from_cbor::<ShareableSecret>(&to_cbor(&enc)).unwrap(), enc);
+  assert_eq!(from_cbor::<SharedKey>(&to_cbor(&key)).unwrap(), key);
+  let cbor = to_cbor(&plain);
+  assert_eq!(cbor[0], 0x50);
+  assert_eq!(from_cbor::<PlainSecret>(&cbor).unwrap(), plain);
+
+  /* plain secrets used to be sequences */
+  let array = to_cbor(&plain.as_bytes().to_vec());
+  assert_eq!(array[0], 0x90);
+  assert_eq!(from_cbor::<PlainSecret>(&array).unwrap(), plain);
+  assert!(from_cbor::<SharedKey>(&to_cbor(&plain)).is_err());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Message {
+  #[serde(with = "rmce::encoding::hex")]
+  enc: ShareableSecret,
+  #[serde(with = "rmce::encoding::bytes")]
+  key: SharedKey,
+  #[serde(with = "rmce::encoding::base64")]
+  plain: PlainSecret,
+}
+
+#[test]
+fn serde_adapters() {
+  let (pk, _) = keypair(2);
+  let (enc, key) = pk.encapsulate_with_entropy_provider(counter(2));
+  let message = Message {
+    enc,
+    key: key.clone(),
+    plain: PlainSecret::from(b"\x00\xff".to_vec()),
+  };
+
+  let json = serde_json::to_value(&message).unwrap();
+  let hex: String = enc.as_bytes().iter().map(|b| format!("{b:02x}")).collect();
+  assert_eq!(json["enc"], hex);
+  assert_eq!(json["key"], serde_json::to_value(key.as_bytes()).unwrap());
+  assert_eq!(json["plain"], "AP8=");
+  assert_eq!(serde_json::from_value::<Message>(json).unwrap(), message);
+
+  let upper = serde_json::json!({
+    "enc": hex.to_uppercase(),
+    "key": serde_json::to_value(key.as_bytes()).unwrap(),
+    "plain": [0, 255],
+  });
+  assert_eq!(serde_json::from_value::<Message>(upper).unwrap(), message);
+  let key_as_string = serde_json::json!({
+    "enc": hex,
+    "key": BASE64.encode(key.as_bytes()),
+    "plain": "AP8=",
+  });
+  assert!(serde_json::from_value::<Message>(key_as_string).is_err());
+  let odd = serde_json::json!({
+    "enc": &hex[1..],
+    "key": serde_json::to_value(key.as_bytes()).unwrap(),
+    "plain": "AP8=",
+  });
+  assert!(serde_json::from_value::<Message>(odd).is_err());
+
+  let cbor = to_cbor(&message);
+  assert_eq!(from_cbor::<Message>(&cbor).unwrap(), message);
+}
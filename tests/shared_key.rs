@@ -0,0 +1,34 @@
+use rmce::*;
+
+#[test]
+fn legacy_session_matches_shared_key() {
+  let keypair_randombytes = include!("resources/keypair_randombytes.in");
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  let (pk, sk) = generate_keypair_with_entropy_provider(|data| {
+    data.copy_from_slice(&keypair_randombytes[0]);
+  });
+
+  let (ct, key) = pk.encapsulate_with_entropy_provider(|data| {
+    data.copy_from_slice(&encrypt_randombytes[0]);
+  });
+  let (ct1, plain) = pk.session_with_entropy_provider(SharedKey::SIZE, |data| {
+    data.copy_from_slice(&encrypt_randombytes[0]);
+  });
+
+  assert_eq!(ct, ct1);
+  assert_eq!(plain.as_bytes(), key.as_bytes());
+  assert_eq!(ct.open(SharedKey::SIZE, &sk).as_bytes(), key.as_bytes());
+}
+
+#[test]
+fn kdf_binds_length() {
+  let key = SharedKey::from([7u8; SharedKey::SIZE]);
+
+  let short = key.kdf(16);
+  let long = key.kdf(64);
+
+  assert_eq!(short.as_bytes().len(), 16);
+  assert_eq!(long.as_bytes().len(), 64);
+  assert_ne!(short.as_bytes(), &long.as_bytes()[..16]);
+  assert_eq!(key.kdf(64), long);
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "stream")]
+
+mod common;
+
+use std::io::{self, Read, Write};
+
+use common::keypair;
+use rmce::stream::*;
+use rmce::*;
+
+/* hands out data in small pieces, like a socket would */
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    let len = out.len().min(self.0.len()).min(7001);
+    out[..len].copy_from_slice(&self.0[..len]);
+    self.0 = &self.0[len..];
+    Ok(len)
+  }
+}
+
+fn encryptor<W: Write>(pk: &PublicKey, inner: W) -> Encryptor<W> {
+  let encrypt_randombytes = include!("resources/encrypt_randombytes.in");
+  Encryptor::with_entropy_provider(pk, inner, |data| {
+    data.copy_from_slice(&encrypt_randombytes[0]);
+  })
+  .unwrap()
+}
+
+fn encrypt(pk: &PublicKey, plaintext: &[u8]) -> Vec<u8> {
+  let mut enc = encryptor(pk, Vec::new());
+  for piece in plaintext.chunks(10_000) {
+    enc.write_all(piece).unwrap();
+  }
+  enc.finish().unwrap()
+}
+
+fn decrypt(sk: &SecretKey, sealed: &[u8]) -> io::Result<Vec<u8>> {
+  let mut plaintext = Vec::new();
+  Decryptor::new(sk, Trickle(sealed))?.read_to_end(&mut plaintext)?;
+  Ok(plaintext)
+}
+
+fn assert_invalid(result: io::Result<Vec<u8>>) {
+  assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn stream_round_trip() {
+  let (pk, sk) = keypair(0);
+  let esk = sk.expand();
+  for len in [
+    0,
+    1,
+    CHUNK_LEN - 1,
+    CHUNK_LEN,
+    CHUNK_LEN + 1,
+    3 * CHUNK_LEN + 5,
+  ] {
+    let plaintext: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+    let sealed = encrypt(&pk, &plaintext);
+    assert_eq!(decrypt(&sk, &sealed).unwrap(), plaintext);
+
+    let mut out = Vec::new();
+    Decryptor::new_expanded(&esk, &sealed[..])
+      .unwrap()
+      .read_to_end(&mut out)
+      .unwrap();
+    assert_eq!(out, plaintext);
+  }
+}
+
+#[test]
+fn stream_detects_truncation_and_reordering() {
+  let (pk, sk) = keypair(1);
+  let (_, other_sk) = keypair(2);
+  let plaintext = vec![0x5a; 3 * CHUNK_LEN + 100];
+  let sealed = encrypt(&pk, &plaintext);
+  let header = sealed.len() - plaintext.len() - 4 * 16;
+  let chunk = CHUNK_LEN + 16;
+
+  assert_invalid(decrypt(&other_sk, &sealed));
+
+  /* cut at a chunk boundary, inside a chunk, and before the tag ends */
+  for len in [
+    header + chunk,
+    header + 2 * chunk,
+    sealed.len() - 1,
+    header + 10,
+  ] {
+    assert_invalid(decrypt(&sk, &sealed[..len]));
+  }
+  assert_invalid(decrypt(&sk, &[&sealed[..], b"x"].concat()));
+
+  let mut swapped = sealed.clone();
+  let (first, second) = swapped[header..header + 2 * chunk].split_at_mut(chunk);
+  first.swap_with_slice(second);
+  assert_invalid(decrypt(&sk, &swapped));
+
+  let mut flipped = sealed.clone();
+  flipped[header + chunk + 3] ^= 1;
+  assert_invalid(decrypt(&sk, &flipped));
+
+  let mut unfinished = Vec::new();
+  let mut enc = encryptor(&pk, &mut unfinished);
+  enc.write_all(&plaintext).unwrap();
+  drop(enc);
+  assert_invalid(decrypt(&sk, &unfinished));
+
+  let mut bad_version = sealed.clone();
+  bad_version[4] = 2;
+  assert_eq!(
+    Decryptor::new(&sk, &bad_version[..]).err().unwrap().kind(),
+    io::ErrorKind::InvalidData
+  );
+}
@@ -0,0 +1,172 @@
+#![cfg(all(feature = "x509", feature = "openssl"))]
+
+mod common;
+
+use std::{str::FromStr, time::Duration};
+
+use common::keypair;
+use openssl::{
+  pkey::{Id, PKey},
+  sign::Signer,
+};
+use rmce::x509::{
+  der::{
+    asn1::{OctetString, UtcTime},
+    DecodePem, EncodePem,
+  },
+  x509_cert::{
+    ext::pkix::AuthorityKeyIdentifier,
+    spki::{AlgorithmIdentifierOwned, ObjectIdentifier},
+  },
+  *,
+};
+use rmce::{Error, PublicKey};
+
+/* the Ed25519 key of tests/resources/x509_ca.pem */
+const CA_SEED: [u8; 32] = [
+  0x73, 0x3a, 0xec, 0x9e, 0xe9, 0x72, 0xda, 0x3d, 0xe5, 0xa6, 0x69, 0x82, 0x95, 0xd3, 0x32, 0xad,
+  0xd1, 0xff, 0x1f, 0xe0, 0xa2, 0xe5, 0x7f, 0xfc, 0xf9, 0x74, 0xd1, 0x73, 0xa4, 0xb7, 0x91, 0xfb,
+];
+const CA_KEY_ID: [u8; 20] = [
+  0xda, 0x37, 0xb0, 0x66, 0xb7, 0xce, 0xe4, 0x5a, 0x79, 0x29, 0xf9, 0x0a, 0xe3, 0x48, 0xb5, 0x74,
+  0x39, 0x8e, 0x80, 0xcf,
+];
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+struct Ed25519Signer(PKey<openssl::pkey::Private>);
+
+impl CertificateSigner for Ed25519Signer {
+  fn signature_algorithm(&self) -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+      oid: ED25519,
+      parameters: None,
+    }
+  }
+  fn sign(&self, tbs_certificate: &[u8]) -> Result<Vec<u8>, Error> {
+    Signer::new_without_digest(&self.0)
+      .and_then(|mut signer| signer.sign_oneshot_to_vec(tbs_certificate))
+      .map_err(|_| Error::SigningFailed)
+  }
+}
+
+fn ca() -> Certificate {
+  Certificate::from_pem(include_str!("resources/x509_ca.pem")).unwrap()
+}
+
+fn kem_certificate(pk: &PublicKey) -> Certificate {
+  let time = |secs| Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(secs)).unwrap());
+  let mut builder = CertificateBuilder::new(
+    pk,
+    SerialNumber::new(&[0x2a]).unwrap(),
+    Validity {
+      /* 2026-01-01 to 2036-01-01 */
+      not_before: time(1_767_225_600),
+      not_after: time(2_082_758_400),
+    },
+    ca().tbs_certificate.subject,
+    Name::from_str("CN=rmce kem server,O=rmce").unwrap(),
+  )
+  .unwrap();
+  builder
+    .add_extension(&AuthorityKeyIdentifier {
+      key_identifier: Some(OctetString::new(CA_KEY_ID).unwrap()),
+      authority_cert_issuer: None,
+      authority_cert_serial_number: None,
+    })
+    .unwrap();
+  let signer = Ed25519Signer(PKey::private_key_from_raw_bytes(&CA_SEED, Id::ED25519).unwrap());
+  builder.sign(&signer).unwrap()
+}
+
+/* lines of `openssl x509 -text` that follow `prefix` */
+fn openssl_text<'a>(text: &'a str, prefix: &'a str) -> impl Iterator<Item = &'a str> {
+  text
+    .lines()
+    .skip_while(move |line| !line.trim_start().starts_with(prefix))
+    .skip(1)
+    .map(str::trim)
+}
+
+fn openssl_field<'a>(text: &'a str, name: &str) -> &'a str {
+  let prefix = format!("{name}: ");
+  text
+    .lines()
+    .find_map(|line| line.trim_start().strip_prefix(prefix.as_str()))
+    .unwrap()
+}
+
+/* x509_kem_certificate.txt is `openssl x509 -noout -text` of the certificate
+ * built by kem_certificate from the first test key pair */
+#[test]
+fn x509_matches_openssl() {
+  let text = include_str!("resources/x509_kem_certificate.txt");
+  let (pk, _) = keypair(0);
+  let certificate = kem_certificate(&pk);
+  let tbs = &certificate.tbs_certificate;
+
+  assert_eq!(openssl_field(text, "Version"), "3 (0x2)");
+  assert_eq!(tbs.version, Version::V3);
+  assert_eq!(openssl_field(text, "Serial Number"), "42 (0x2a)");
+  assert_eq!(tbs.serial_number.as_bytes(), [0x2a]);
+  /* openssl prints names most significant RDN first, RFC 4514 the other way */
+  let openssl_name = |name: &Name| {
+    let rdns: Vec<String> = name.0.iter().map(|rdn| rdn.to_string()).collect();
+    rdns.join(", ")
+  };
+  assert_eq!(openssl_field(text, "Issuer"), openssl_name(&tbs.issuer));
+  assert_eq!(openssl_field(text, "Subject"), openssl_name(&tbs.subject));
+  assert_eq!(
+    openssl_field(text, "Public Key Algorithm"),
+    rmce::asn1::OID.to_string()
+  );
+  assert_eq!(
+    openssl_text(text, "X509v3 Key Usage: critical").next(),
+    Some("Key Encipherment")
+  );
+  let signature: String = openssl_text(text, "Signature Value:")
+    .collect::<String>()
+    .replace(':', "");
+  let ours: String = certificate
+    .signature
+    .raw_bytes()
+    .iter()
+    .map(|b| format!("{b:02x}"))
+    .collect();
+  assert_eq!(signature, ours);
+
+  /* and openssl accepts the issuer's signature */
+  let ca = openssl::x509::X509::from_pem(include_bytes!("resources/x509_ca.pem")).unwrap();
+  let ca_key = ca.public_key().unwrap();
+  let mut verifier = openssl::sign::Verifier::new_without_digest(&ca_key).unwrap();
+  let tbs_der = rmce::x509::der::Encode::to_der(tbs).unwrap();
+  assert!(verifier
+    .verify_oneshot(certificate.signature.raw_bytes(), &tbs_der)
+    .unwrap());
+}
+
+#[test]
+fn x509_public_key_round_trip() {
+  let (pk, _) = keypair(1);
+  let certificate = kem_certificate(&pk);
+  assert_eq!(public_key(&certificate).unwrap(), pk);
+  let pem = certificate.to_pem(Default::default()).unwrap();
+  assert_eq!(public_key_from_pem(&pem).unwrap(), pk);
+  let der = rmce::x509::der::Encode::to_der(&certificate).unwrap();
+  assert_eq!(public_key_from_der(&der).unwrap(), pk);
+
+  assert!(matches!(public_key(&ca()), Err(Error::AlgorithmMismatch)));
+  let mut other_set = certificate.clone();
+  other_set
+    .tbs_certificate
+    .subject_public_key_info
+    .algorithm
+    .oid = rmce::asn1::ParameterSet::Mceliece6960119f.oid();
+  assert!(matches!(
+    public_key(&other_set),
+    Err(Error::AlgorithmMismatch)
+  ));
+  assert!(matches!(
+    public_key_from_der(&der[..der.len() - 1]),
+    Err(Error::InvalidEncoding)
+  ));
+}